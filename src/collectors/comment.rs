@@ -10,16 +10,13 @@ use crate::privat::PeekableCharTracker;
 ///
 /// # Returns
 ///
-/// * `String` - The collected comment.
-pub fn collect_comment(iter: &mut PeekableCharTracker) -> String {
-    let mut comment = String::new();
+/// * `&str` - The collected comment.
+pub fn collect_comment<'a>(iter: &mut PeekableCharTracker<'a>) -> &'a str {
+    let start = iter.offset();
 
     // Iterate over the characters in the iterator until a newline character is encountered.
-    while let Some(c) = iter.next_if(|c| *c != '\n') {
-        // Add the current character to the comment string.
-        comment.push(c);
-    }
+    while iter.next_if(|c| *c != '\n' && *c != '\r').is_some() {}
 
     // Return the collected comment.
-    comment
-}
\ No newline at end of file
+    iter.slice_from(start)
+}
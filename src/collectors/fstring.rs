@@ -1,130 +1,123 @@
-use crate::{privat::PeekableCharTracker, tokenize, Result, Token, TokenizeError};
+use crate::collectors::collect_escape;
+use crate::{
+    privat::PeekableCharTracker, ErrorKind, Result, TokenizeError, TokenizeWarning, WarningKind,
+};
 
-/// Collects an f-string literal from the input iterator and adds tokens to the provided vector.
+/// Collects the literal text of an f-string, or the format spec of one of its
+/// replacement fields, up to the brace or the quotes that end it.
+///
+/// In the literal text a doubled brace stands for a single one and is kept as it is.
 ///
 /// # Arguments
 ///
 /// * `iter` - A mutable reference to the PeekableCharTracker iterator.
-/// * `tokens` - A mutable reference to the vector of tokens to store the collected tokens.
-/// * `prefix` - The prefix character indicating the type of f-string ('f', 'F').
+/// * `quote` - The quotes that end the f-string.
+/// * `raw` - Whether the f-string is raw, so its backslashes do not start escape sequences.
+/// * `format_spec` - Whether the text is a format spec, which ends with a brace.
+/// * `template` - Whether the string is a t-string rather than an f-string.
+/// * `start` - The position of the f-string, where its unterminated errors start.
+/// * `warnings` - A sink for the warnings about invalid escape sequences.
 ///
 /// # Returns
 ///
-/// A Result indicating success or an error of type TokenizeError.
+/// A Result containing the collected text, which may be empty, or an error.
 ///
 /// # Errors
 ///
-/// * `TokenizeError::*` - for many reasons
-pub fn collect_fstring(
-    iter: &mut PeekableCharTracker,
-    tokens: &mut Vec<Token>,
-    prefix: char,
-) -> Result<()> {
-    // Get the first quote character
-    let quot = iter.next().unwrap();
-
-    // Check if the f-string is a multi-line f-string
-    let multi_line = {
-        let mut iter_clone = iter.clone();
-        iter_clone.next() == Some(quot) && iter_clone.next() == Some(quot)
-    };
-
-    // Create a new FStringStart token and push it to the tokens vector
-    tokens.push(Token::FStringStart(format!(
-        "{}{}",
-        prefix,
-        if multi_line {
-            iter.nth(1);
-            quot.to_string().repeat(3)
-        } else {
-            quot.to_string()
+/// * `ErrorKind::UnterminatedFString`, `ErrorKind::UnterminatedTripleQuotedFString`
+///   or their t-string counterparts - If the f-string is not closed.
+/// * `ErrorKind::SingleClosingBrace` - If the literal text has a `}` that is not doubled.
+/// * `ErrorKind::ExpectingClosingBrace` - If the f-string ends in a format spec.
+/// * For the same reasons as [`collect_escape`].
+pub fn collect_fstring_middle<'a>(
+    iter: &mut PeekableCharTracker<'a>,
+    quote: &str,
+    raw: bool,
+    format_spec: bool,
+    template: bool,
+    start: (usize, usize),
+    warnings: &mut Vec<TokenizeWarning>,
+) -> Result<&'a str> {
+    let offset = iter.offset();
+    let triple = quote.len() == 3;
+    while let Some(&c) = iter.peek() {
+        if iter.src()[iter.offset()..].starts_with(quote) {
+            if format_spec {
+                return Err(TokenizeError::at(
+                    ErrorKind::ExpectingClosingBrace,
+                    iter.pos(),
+                ));
+            }
+            return Ok(iter.slice_from(offset));
         }
-    )));
-
-    // Continue iterating through the characters in the f-string
-    while let Some(c) = iter.next_if(|c| *c != quot) {
         match c {
-            '{' => {
-                let mut inner = String::new();
-                inner.push('{');
-                while let Some(c) = iter.next_if(|c| *c != '}') {
-                    inner.push(c);
-                }
-                inner.push(iter.next().unwrap());
-                tokens.extend(match tokenize(inner) {
-                    Ok(i) => i,
-                    Err(e) => {
-                        return Err({
-                            let pos = iter.pos();
-                            use TokenizeError::*;
-                            match e {
-                                EscapeSeq(msg, (iter_num, char_num)) => {
-                                    EscapeSeq(msg, (iter_num + pos.0, char_num + pos.1))
-                                }
-                                String(msg, (iter_num, char_num)) => {
-                                    String(msg, (iter_num + pos.0, char_num + pos.1))
-                                }
-                                Number(msg, (iter_num, char_num)) => {
-                                    Number(msg, (iter_num + pos.0, char_num + pos.1))
-                                }
-                                Operator(msg, (iter_num, char_num)) => {
-                                    Operator(msg, (iter_num + pos.0, char_num + pos.1))
-                                }
-                                Char(msg, (iter_num, char_num)) => {
-                                    Char(msg, (iter_num + pos.0, char_num + pos.1))
-                                }
-                                Indent(msg, (iter_num, char_num)) => {
-                                    Indent(msg, (iter_num + pos.0, char_num + pos.1))
-                                }
-                                EndOfFile(msg, (iter_num, char_num)) => {
-                                    EndOfFile(msg, (iter_num + pos.0, char_num + pos.1))
-                                }
-                            }
-                        })
-                    }
-                });
-                tokens.pop(); // Delete NewLine
-                tokens.pop(); // Delente EndMarker
+            '{' | '}' if !format_spec && iter.clone().nth(1) == Some(c) => {
+                iter.nth(1);
+            }
+            '{' => return Ok(iter.slice_from(offset)),
+            '}' if format_spec => return Ok(iter.slice_from(offset)),
+            '}' => return Err(TokenizeError::at(ErrorKind::SingleClosingBrace, iter.pos())),
+            '\n' | '\r' if !triple => {
+                let kind = unterminated(triple, template);
+                return Err(TokenizeError::new(kind, start, iter.pos()));
             }
-            c => {
-                let mut fstring_midle = String::new();
-                fstring_midle.push(c);
-                while let Some(c) = iter.next_if(|c| *c != quot && *c != '{') {
-                    if c == '\n' && !multi_line {
-                        return Err(TokenizeError::String(
-                            "Unterminated f-string literal".to_owned(),
-                            iter.pos(),
-                        ));
-                    }
-                    fstring_midle.push(c);
-                }
-                if !fstring_midle.is_empty() {
-                    tokens.push(Token::FStringMiddle(fstring_midle))
-                }
+            '\\' => {
+                iter.next();
+                collect_backslash(iter, raw, warnings)?;
+            }
+            _ => {
+                iter.next();
             }
         }
     }
-    // Create a new FStringEnd token and push it to the tokens vector
-    tokens.push(Token::FStringEnd(if multi_line {
-        // Move the iterator forward by 3 to skip the closing triple-quote characters
-        iter.next();
-        match (iter.next(), iter.next()) {
-            // If the closing triple-quote characters are found, create a string of 3 quote characters
-            (Some(second), Some(third)) if second == third && third == quot => {}
-            // Otherwise, return an error
-            _ => {
-                return Err(TokenizeError::String(
-                    "Unterminated triple-quoted f-string literal".to_owned(),
-                    iter.pos(),
-                ))
+
+    // The f-string is not closed
+    Err(TokenizeError::new(
+        unterminated(triple, template),
+        start,
+        iter.pos(),
+    ))
+}
+
+/// Returns the kind of error for an f-string or a t-string that is not closed.
+pub fn unterminated(triple: bool, template: bool) -> ErrorKind {
+    match (triple, template) {
+        (false, false) => ErrorKind::UnterminatedFString,
+        (true, false) => ErrorKind::UnterminatedTripleQuotedFString,
+        (false, true) => ErrorKind::UnterminatedTString,
+        (true, true) => ErrorKind::UnterminatedTripleQuotedTString,
+    }
+}
+
+/// Collects the character after a backslash in the literal part of an f-string,
+/// so that an escaped quote does not end the f-string.
+///
+/// A brace after a backslash still starts or ends a replacement field,
+/// which is an invalid escape sequence outside of raw f-strings.
+/// Other escape sequences are checked as in [`collect_escape`].
+fn collect_backslash(
+    iter: &mut PeekableCharTracker,
+    raw: bool,
+    warnings: &mut Vec<TokenizeWarning>,
+) -> Result<()> {
+    match iter.peek().copied() {
+        Some(brace @ ('{' | '}')) => {
+            if !raw {
+                let (line, column) = iter.pos();
+                let kind = WarningKind::InvalidEscapeSequence(format!("\\{}", brace));
+                warnings.push(TokenizeWarning::new(
+                    kind,
+                    (line, column - 1),
+                    (line, column + 1),
+                ));
             }
+            Ok(())
+        }
+        None => Ok(()),
+        Some(_) if raw => {
+            iter.next();
+            Ok(())
         }
-        quot.to_string().repeat(3)
-    } else {
-        // Move the iterator forward by 1 to skip the closing quote character
-        iter.next();
-        // Create a string of 1 quote character
-        quot.to_string()
-    }));
-    Ok(())
+        Some(_) => collect_escape(iter, false, warnings).map(|_| ()),
+    }
 }
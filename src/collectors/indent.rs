@@ -1,4 +1,37 @@
-use crate::PeekableCharTracker;
+use crate::privat::PeekableCharTracker;
+
+/// The depth of an indentation, measured in two ways as CPython does.
+///
+/// Two indentations that compare differently in the two ways
+/// depend on the width of a tab, which is an error.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Indentation {
+    /// The column with tabs to the next multiple of 8.
+    pub col: usize,
+    /// The column with tabs one column wide.
+    pub alt_col: usize,
+}
+
+impl Indentation {
+    /// Measures an indentation, where a form feed starts the columns over.
+    pub fn of(indent: &str) -> Self {
+        let mut indentation = Indentation::default();
+        for c in indent.chars() {
+            match c {
+                '\t' => {
+                    indentation.col = (indentation.col / 8 + 1) * 8;
+                    indentation.alt_col += 1;
+                }
+                '\x0c' => indentation = Indentation::default(),
+                _ => {
+                    indentation.col += 1;
+                    indentation.alt_col += 1;
+                }
+            }
+        }
+        indentation
+    }
+}
 
 /// Collects an indent from the input iterator.
 ///
@@ -8,17 +41,10 @@ use crate::PeekableCharTracker;
 ///
 /// # Returns
 ///
-/// * `String` - A `String` containing the collected indent.
+/// * `&str` - A `&str` containing the collected indent.
 ///
-pub fn collect_indent(iter: &mut PeekableCharTracker) -> String {
-    let mut new_indent = String::new();
-    while let Some(c2) = iter.peek() {
-        match c2 {
-            '\t' => new_indent.push('\t'),
-            ' ' => new_indent.push(' '),
-            _ => break,
-        }
-        iter.next();
-    }
-    new_indent
+pub fn collect_indent<'a>(iter: &mut PeekableCharTracker<'a>) -> &'a str {
+    let start = iter.offset();
+    while iter.next_if(|c| matches!(c, '\t' | ' ' | '\x0c')).is_some() {}
+    iter.slice_from(start)
 }
@@ -1,10 +1,11 @@
 pub use comment::collect_comment;
-pub use fstring::collect_fstring;
-pub use indent::collect_indent;
-pub use name::collect_name;
+pub use fstring::{collect_fstring_middle, unterminated};
+pub use indent::{collect_indent, Indentation};
+pub use name::{collect_name, is_name_start, normalize_name};
 pub use number::collect_number;
 pub use operator::collect_operator;
-pub use string::collect_string;
+pub use string::{collect_escape, collect_string, Escape};
+pub use whitespace::collect_whitespace;
 
 mod comment;
 mod fstring;
@@ -13,3 +14,4 @@ mod name;
 mod number;
 mod operator;
 mod string;
+mod whitespace;
@@ -1,30 +1,52 @@
-use crate::{privat::PeekableCharTracker, OPERATORS};
+use std::borrow::Cow;
+
+use unicode_normalization::{is_nfkc_quick, IsNormalized, UnicodeNormalization};
+
+use crate::privat::PeekableCharTracker;
+
+/// Checks if a character can start a name, as PEP 3131 defines:
+/// an underscore or a character with the `XID_Start` property.
+pub fn is_name_start(c: char) -> bool {
+    c == '_' || unicode_ident::is_xid_start(c)
+}
+
+/// Checks if a character can go on a name: an underscore
+/// or a character with the `XID_Continue` property.
+pub fn is_name_continue(c: char) -> bool {
+    c == '_' || unicode_ident::is_xid_continue(c)
+}
 
 /// Collects a name from the given iterator.
 ///
-/// The name is collected until a whitespace or an operator is encountered.
-/// If a prefix is provided, it is added to the beginning of the name.
+/// The name is collected until a character that can not go on a name is encountered.
+/// Characters already consumed since `start` (such as a string prefix
+/// that turned out to be a name) are part of the name.
 ///
 /// # Arguments
 ///
 /// * `iter` - A mutable reference to a `PeekableCharTracker` iterator.
-/// * `prefix` - An optional prefix character.
+/// * `start` - The byte offset where the name begins.
 ///
 /// # Returns
 ///
-/// * `String` - The collected name.
-pub fn collect_name(iter: &mut PeekableCharTracker, prefix: Option<char>) -> String {
-    let mut name = String::new();
+/// * `&str` - The collected name.
+pub fn collect_name<'a>(iter: &mut PeekableCharTracker<'a>, start: usize) -> &'a str {
+    while iter.next_if(|c| is_name_continue(*c)).is_some() {}
 
-    // If a prefix is provided, add it to the beginning of the name.
-    if let Some(prefix) = prefix {
-        name.push(prefix);
-    }
+    iter.slice_from(start)
+}
 
-    // Collect characters from the iterator until a whitespace or an operator is encountered.
-    while let Some(c) = iter.next_if(|c| !c.is_whitespace() && !OPERATORS.contains(*c)) {
-        name.push(c);
+/// Returns the NFKC form of a name, which Python compares names by,
+/// borrowing the name when it is already normalized.
+pub fn normalize_name(name: &str) -> Cow<'_, str> {
+    if name.is_ascii() || is_nfkc_quick(name.chars()) == IsNormalized::Yes {
+        Cow::Borrowed(name)
+    } else {
+        let normalized: String = name.nfkc().collect();
+        if normalized == name {
+            Cow::Borrowed(name)
+        } else {
+            Cow::Owned(normalized)
+        }
     }
-
-    name
-}
\ No newline at end of file
+}
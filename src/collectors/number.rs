@@ -1,74 +1,164 @@
-use crate::{privat::PeekableCharTracker, Result, TokenizeError};
+use crate::{privat::PeekableCharTracker, ErrorKind, Result, TokenizeError};
+
+/// Keywords that may directly follow a number, as in `1if x else 2`.
+const KEYWORDS_AFTER_NUMBER: [&str; 8] = ["and", "else", "for", "if", "in", "is", "not", "or"];
 
 /// Collects a number as a Python tokenizer.
 ///
+/// All the numeric literals of Python are recognized: decimal, hexadecimal,
+/// octal and binary integers, floats with an optional exponent and imaginary numbers,
+/// with single underscores allowed between digits.
+///
 /// # Arguments
 ///
 /// * `iter` - A mutable reference to the PeekableCharTracker iterator.
-/// * `digit` - An optional starting digit character.
+/// * `start` - The byte offset where the number begins,
+///   which may be before an already consumed leading `.`.
 ///
 /// # Returns
 ///
-/// A Result containing the collected number as a String or an error.
+/// A Result containing the collected number as a `&str` or an error.
+///
+/// # Errors
 ///
-pub fn collect_number(iter: &mut PeekableCharTracker, digit: Option<char>) -> Result<String> {
-    let mut number = String::new();
+/// * `ErrorKind::InvalidDecimalLiteral` - If a decimal integer or a float is malformed.
+/// * `ErrorKind::InvalidHexadecimalLiteral`, `ErrorKind::InvalidOctalLiteral`
+///   or `ErrorKind::InvalidBinaryLiteral` - If an integer with a radix prefix
+///   has no digits or is followed by a letter.
+/// * `ErrorKind::InvalidDigit` - If an octal or binary integer has a digit out of its radix.
+/// * `ErrorKind::LeadingZeros` - If a decimal integer other than zero starts with `0`.
+/// * `ErrorKind::InvalidImaginaryLiteral` - If an imaginary number is followed by a letter.
+pub fn collect_number<'a>(iter: &mut PeekableCharTracker<'a>, start: usize) -> Result<&'a str> {
+    let (line, column) = iter.pos();
+    let start_pos = (line, column - iter.slice_from(start).len());
 
-    // Add the starting digit if provided
-    if let Some(d) = digit {
-        number.push(d);
+    // A float like `.5` only has a fraction
+    if iter.slice_from(start) == "." {
+        collect_digits(iter, 10)?;
+        collect_float_end(iter, false)?;
+        return Ok(iter.slice_from(start));
     }
 
-    // Iterate over characters to collect the number
-    while let Some(c) = iter.peek() {
-        match c {
-            '0'..='9' => {
-                number.push(iter.next().unwrap());
+    if iter.peek() == Some(&'0') {
+        let radix = match iter.clone().nth(1) {
+            Some('x' | 'X') => 16,
+            Some('o' | 'O') => 8,
+            Some('b' | 'B') => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            iter.nth(1);
+            if collect_digits(iter, radix)? == 0 || matches!(iter.peek(), Some('0'..='9')) {
+                return Err(invalid_digit(iter, radix));
             }
-            '_' => {
-                iter.next();
-                match iter.peek() {
-                    Some('0'..='9') => number.push('_'),
-                    _ => {
-                        return Err(TokenizeError::Number(
-                            "Invalid decimal literal".to_owned(),
-                            iter.pos(),
-                        ));
-                    }
-                }
+            verify_end(iter, radix_kind(radix))?;
+            return Ok(iter.slice_from(start));
+        }
+    }
+
+    collect_digits(iter, 10)?;
+    match iter.peek() {
+        Some('.' | 'e' | 'E' | 'j' | 'J') => collect_float_end(iter, true)?,
+        _ => {
+            let number = iter.slice_from(start);
+            if number.starts_with('0') && number.contains(|c: char| ('1'..='9').contains(&c)) {
+                return Err(TokenizeError::new(
+                    ErrorKind::LeadingZeros,
+                    start_pos,
+                    iter.pos(),
+                ));
             }
-            '.' if !number.contains('.') => {
-                number.push(iter.next().unwrap());
-                match iter.peek() {
-                    Some('0'..='9') => continue,
-                    _ => break,
-                }
+            verify_end(iter, ErrorKind::InvalidDecimalLiteral)?;
+        }
+    }
+    Ok(iter.slice_from(start))
+}
+
+/// Collects digits of the given radix separated by single underscores,
+/// returning the number of the digits.
+fn collect_digits(iter: &mut PeekableCharTracker, radix: u32) -> Result<usize> {
+    let mut count = 0;
+    while let Some(&c) = iter.peek() {
+        if c == '_' {
+            iter.next();
+            match iter.peek() {
+                Some(c) if c.is_digit(radix) => {}
+                _ => return Err(invalid_digit(iter, radix)),
+            }
+        } else if !c.is_digit(radix) {
+            break;
+        }
+        iter.next();
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Collects the rest of a float after its integer part: the fraction if `point` allows it,
+/// the exponent and the `j` of an imaginary number, each of them optional.
+fn collect_float_end(iter: &mut PeekableCharTracker, point: bool) -> Result<()> {
+    if point && iter.next_if(|c| *c == '.').is_some() && matches!(iter.peek(), Some('0'..='9')) {
+        collect_digits(iter, 10)?;
+    }
+    if let Some('e' | 'E') = iter.peek() {
+        let mut ahead = iter.clone();
+        ahead.next();
+        let sign = ahead.next_if(|c| *c == '+' || *c == '-').is_some();
+        match ahead.peek() {
+            Some('0'..='9') => {
+                *iter = ahead;
+                collect_digits(iter, 10)?;
             }
-            'j' => {
-                number.push(iter.next().unwrap());
-                break;
+            _ if sign => {
+                iter.nth(1);
+                return Err(TokenizeError::at(
+                    ErrorKind::InvalidDecimalLiteral,
+                    iter.pos(),
+                ));
             }
-            'e' if !number.contains('e') => {
-                match iter.clone().nth(1) {
-                    Some('-' | '+') => {
-                        number.push(iter.next().unwrap());
-                        number.push(iter.next().unwrap());
-                        match iter.peek() {
-                            Some('0'..='9') => continue,
-                            _ => {
-                                return Err(TokenizeError::Number(
-                                    "Invalid decimal literal".to_owned(),
-                                    iter.pos(),
-                                ));
-                            }
-                        }
-                    }
-                    _ => break,
-                }
+            // The `e` either starts a keyword or makes the number invalid
+            _ => {}
+        }
+    }
+    if iter.next_if(|c| *c == 'j' || *c == 'J').is_some() {
+        return verify_end(iter, ErrorKind::InvalidImaginaryLiteral);
+    }
+    verify_end(iter, ErrorKind::InvalidDecimalLiteral)
+}
+
+/// Checks that a number is not directly followed by a name, except for some keywords.
+fn verify_end(iter: &mut PeekableCharTracker, kind: ErrorKind) -> Result<()> {
+    match iter.peek() {
+        Some(&c) if c.is_alphanumeric() || c == '_' => {
+            let rest = &iter.src()[iter.offset()..];
+            if KEYWORDS_AFTER_NUMBER.iter().any(|keyword| rest.starts_with(keyword)) {
+                Ok(())
+            } else {
+                Err(TokenizeError::at(kind, iter.pos()))
             }
-            _ => break,
         }
+        _ => Ok(()),
+    }
+}
+
+/// Returns the kind of error for a malformed integer of the given radix.
+fn radix_kind(radix: u32) -> ErrorKind {
+    match radix {
+        16 => ErrorKind::InvalidHexadecimalLiteral,
+        8 => ErrorKind::InvalidOctalLiteral,
+        2 => ErrorKind::InvalidBinaryLiteral,
+        _ => ErrorKind::InvalidDecimalLiteral,
     }
+}
 
-    Ok(number)
-}
\ No newline at end of file
+/// Makes the error for the character at the current position,
+/// which is not a digit of the given radix.
+fn invalid_digit(iter: &mut PeekableCharTracker, radix: u32) -> TokenizeError {
+    let pos = iter.pos();
+    match iter.peek() {
+        Some(&digit) if radix < 10 && digit.is_ascii_digit() => {
+            TokenizeError::at(ErrorKind::InvalidDigit { digit, radix }, pos)
+        }
+        _ => TokenizeError::at(radix_kind(radix), pos),
+    }
+}
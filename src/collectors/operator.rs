@@ -1,7 +1,10 @@
-use crate::{privat::PeekableCharTracker, Result, TokenizeError, OPERATORS};
+use crate::{privat::PeekableCharTracker, ErrorKind, OperatorKind, Result, TokenizeError};
 
 /// Collects an operator from the input iterator.
 ///
+/// The longest operator of Python that the text starts with is collected,
+/// so `**=` is one operator and `..` is two.
+///
 /// # Arguments
 ///
 /// * `iter` - A mutable reference to a `PeekableCharTracker` instance.
@@ -9,57 +12,29 @@ use crate::{privat::PeekableCharTracker, Result, TokenizeError, OPERATORS};
 ///
 /// # Returns
 ///
-/// * `Result<String>` - A `Result` containing the collected operator as a `String`.
+/// * `Result<&str>` - A `Result` containing the collected operator as a `&str`.
 ///
 /// # Errors
 ///
-/// * `TokenizeError::Operator` - If the operator is invalid.
-pub fn collect_operator(iter: &mut PeekableCharTracker, operator: char) -> Result<String> {
-    // Check if the operator is valid.
-    if !OPERATORS.contains(operator) {
-        // If not, return an error.
-        Err(TokenizeError::Operator(
-            format!("Invalid operator: {:?}", operator),
-            iter.pos(),
-        ))
-    } else {
-        // If the operator is valid, proceed to the next step.
-        Ok(match (operator, iter.peek()) {
-            // If the next character is '=', and the current operator is one of "+-*/%&|<>!^:@",
-            // then collect the operator and the '=' character.
-            (c, Some('=')) if "=+-*/%&|<>!^:@".contains(c) => {
-                iter.next();
-                format!("{}=", c)
-            }
-            // If the current operator is '-', and the next character is '>',
-            // then collect the '->' operator.
-            ('-', Some('>')) => {
-                iter.next();
-                "->".to_owned()
-            }
-            // If the current operator is '<', and the next character is '>',
-            // then collect the '<>' operator.
-            ('<', Some('>')) => {
-                iter.next();
-                "<>".to_owned()
-            }
-            // If the current operator is the same as the next character, and the current operator is one of "*/<>",
-            // then collect the operator and the next character.
-            (c1, Some(c2)) if c1 == *c2 && "*/<>".contains(c1) => {
-                let c2 = *c2;
+/// * `ErrorKind::InvalidCharacter` - If the operator is invalid.
+pub fn collect_operator<'a>(iter: &mut PeekableCharTracker<'a>, operator: char) -> Result<&'a str> {
+    let start = iter.offset() - operator.len_utf8();
+    let rest = &iter.src()[start..];
+
+    // Python's operators are at most three characters long.
+    let len =
+        (1..=3).rev().find(|len| rest.get(..*len).and_then(OperatorKind::from_operator).is_some());
+    match len {
+        Some(len) => {
+            for _ in 1..len {
                 iter.next();
-                match iter.peek() {
-                    // If the next character is '=', then collect the operator and the '=' character.
-                    Some('=') => {
-                        iter.next();
-                        format!("{}{}=", c1, c2)
-                    }
-                    // Otherwise, just collect the operator and the next character.
-                    _ => format!("{}{}", c1, c2),
-                }
             }
-            // If none of the above conditions are met, then just collect the current operator.
-            (c, _) => c.to_string(),
-        })
+            Ok(iter.slice_from(start))
+        }
+        None => Err(TokenizeError::new(
+            ErrorKind::InvalidCharacter(operator),
+            (iter.pos().0, iter.pos().1 - 1),
+            iter.pos(),
+        )),
     }
 }
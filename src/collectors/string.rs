@@ -1,35 +1,52 @@
-use crate::{privat::PeekableCharTracker, Result, TokenizeError};
+use crate::{
+    privat::PeekableCharTracker, ErrorKind, Result, TokenizeError, TokenizeWarning, WarningKind,
+};
+
+/// The prefixes of strings other than f-strings, in any case.
+const STRING_PREFIXES: [&str; 6] = ["", "u", "b", "r", "br", "rb"];
 
 /// Collects a string from the iterator.
 ///
+/// Escape sequences of strings that are not raw are checked as they are collected.
+///
 /// # Arguments
 ///
 /// * `iter` - A mutable reference to the PeekableCharTracker iterator.
-/// * `prefix` - An optional prefix character.
+/// * `start` - The byte offset where the string begins,
+///   which is before its prefix if the prefix was already consumed.
+/// * `warnings` - A sink for the warnings about invalid escape sequences.
 ///
 /// # Returns
 ///
 /// A Result containing the collected string or an error.
 ///
-pub fn collect_string(iter: &mut PeekableCharTracker, prefix: Option<char>) -> Result<String> {
-    let mut string = String::new();
+/// # Errors
+///
+/// * `ErrorKind::InvalidStringPrefix` - If the prefix is not a string prefix.
+/// * `ErrorKind::UnterminatedString` or `ErrorKind::UnterminatedTripleQuotedString` -
+///   If the string is not closed.
+/// * `ErrorKind::NonAsciiBytes` - If a bytes literal has a character outside of ASCII.
+/// * For the same reasons as [`collect_escape`].
+pub fn collect_string<'a>(
+    iter: &mut PeekableCharTracker<'a>,
+    start: usize,
+    warnings: &mut Vec<TokenizeWarning>,
+) -> Result<&'a str> {
+    // The prefix is on the same line, one column per character
+    let (line, column) = iter.pos();
+    let start_pos = (line, column - iter.slice_from(start).chars().count());
 
-    // Handle prefix character if present
-    if let Some(prefix) = prefix {
-        match prefix {
-            'u' | 'U' | 'b' | 'B' | 'r' | 'R' => string.push(prefix),
-            prefix => {
-                return Err(TokenizeError::String(
-                    format!("Invalid prefix: {:?}", prefix),
-                    iter.pos(),
-                ))
-            }
-        }
+    // Handle prefix characters if present
+    let prefix = iter.slice_from(start);
+    if !STRING_PREFIXES.iter().any(|p| prefix.eq_ignore_ascii_case(p)) {
+        let kind = ErrorKind::InvalidStringPrefix(prefix.to_owned());
+        return Err(TokenizeError::new(kind, start_pos, iter.pos()));
     }
+    let raw = prefix.contains(['r', 'R']);
+    let bytes = prefix.contains(['b', 'B']);
 
-    // Push the starting quote character
+    // Skip the starting quote character
     let quot = iter.next().unwrap();
-    string.push(quot);
 
     // Check if it's a multiline string
     let multi_line = {
@@ -39,52 +56,184 @@ pub fn collect_string(iter: &mut PeekableCharTracker, prefix: Option<char>) -> R
 
     // Handle multi line string quotes
     if multi_line {
-        string.push(iter.next().unwrap());
-        string.push(iter.next().unwrap());
+        iter.nth(1);
     }
 
     // Iterate over characters in the string
-    while let Some(c) = iter.peek() {
+    while let Some(&c) = iter.peek() {
         match c {
-            '\n' if !multi_line => {
-                return Err(TokenizeError::String(
-                    "Unterminated string literal".to_owned(),
-                    iter.pos(),
-                ))
+            '\n' | '\r' if !multi_line => break,
+            // A backslash always keeps the next character from ending the string
+            '\\' => {
+                iter.next();
+                if bytes && iter.peek().is_some_and(|c| !c.is_ascii()) {
+                    return Err(TokenizeError::at(ErrorKind::NonAsciiBytes, iter.pos()));
+                }
+                if raw {
+                    // The backslash keeps a whole `\r\n` in the string
+                    if iter.next() == Some('\r') {
+                        iter.next_if(|c| *c == '\n');
+                    }
+                } else {
+                    collect_escape(iter, bytes, warnings)?;
+                }
+            }
+            c if bytes && !c.is_ascii() => {
+                return Err(TokenizeError::at(ErrorKind::NonAsciiBytes, iter.pos()));
             }
             c => {
-                // Check for closing quote
-                if *c == quot {
-                    string.push(iter.next().unwrap()); // Handle quote
-                    if multi_line {
-                        // Check for closing quotes in multiline string
-                        match (iter.next(), iter.next()) {
-                            (Some(second), Some(third)) if second == third && third == quot => {
-                                string.push_str(&format!("{}{}", second, third))
-                            }
-                            _ => {
-                                return Err(TokenizeError::EndOfFile(
-                                    "EOF in multi-line string".to_owned(),
-                                    iter.pos(),
-                                ))
-                            }
-                        }
+                iter.next();
+                // Check for closing quotes
+                if c == quot {
+                    if !multi_line {
+                        return Ok(iter.slice_from(start));
+                    }
+                    let mut ahead = iter.clone();
+                    if ahead.next() == Some(quot) && ahead.next() == Some(quot) {
+                        *iter = ahead;
+                        return Ok(iter.slice_from(start));
                     }
-                    break;
                 }
-                string.push(*c);
             }
         }
-        iter.next();
     }
 
-    // Check for unclosed string
-    if string.chars().filter(|c| *c == quot).count() < 2 {
-        return Err(TokenizeError::String(
-            "Unterminated string literal".to_owned(),
-            iter.pos(),
-        ));
-    }
+    // The string is not closed
+    let kind = if multi_line {
+        ErrorKind::UnterminatedTripleQuotedString
+    } else {
+        ErrorKind::UnterminatedString
+    };
+    Err(TokenizeError::new(kind, start_pos, iter.pos()))
+}
+
+/// An escape sequence collected by [`collect_escape`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Escape<'a> {
+    /// An escape sequence standing for the character or the byte with the given value.
+    Value(u32),
+    /// A `\N{...}` escape sequence, standing for the character with the given name.
+    Named(&'a str),
+    /// A backslash at the end of a line, which joins the line with the next one.
+    LineJoin,
+    /// An escape sequence that Python does not know, which stands for itself.
+    Unknown,
+}
 
-    Ok(string)
+/// Collects an escape sequence of a string that is not raw, after its backslash.
+///
+/// Escape sequences that Python does not know are kept as they are,
+/// with a warning like the `SyntaxWarning` of CPython 3.12.
+///
+/// # Arguments
+///
+/// * `iter` - A mutable reference to the PeekableCharTracker iterator.
+/// * `bytes` - Whether the escape sequence is in a bytes literal,
+///   which has no `\u`, `\U` and `\N` escapes.
+/// * `warnings` - A sink for the warnings about invalid escape sequences.
+///
+/// # Returns
+///
+/// A Result containing what the escape sequence stands for or an error.
+///
+/// # Errors
+///
+/// * `ErrorKind::TruncatedEscape` - If `\x`, `\u` or `\U` has too few hexadecimal digits.
+/// * `ErrorKind::IllegalUnicodeCharacter` - If `\U` is above the largest Unicode character.
+/// * `ErrorKind::MalformedNamedEscape` - If `\N` is not followed by a name in braces.
+pub fn collect_escape<'a>(
+    iter: &mut PeekableCharTracker<'a>,
+    bytes: bool,
+    warnings: &mut Vec<TokenizeWarning>,
+) -> Result<Escape<'a>> {
+    let (line, column) = iter.pos();
+    let start = (line, column - 1);
+    let offset = iter.offset() - 1;
+
+    let c = match iter.next() {
+        Some(c) => c,
+        None => return Ok(Escape::Unknown),
+    };
+    let value = match c {
+        '\n' => return Ok(Escape::LineJoin),
+        // A line ending with `\r\n` or `\r` is joined like one ending with `\n`
+        '\r' => {
+            iter.next_if(|c| *c == '\n');
+            return Ok(Escape::LineJoin);
+        }
+        '\\' | '\'' | '"' => c as u32,
+        'a' => 0x07,
+        'b' => 0x08,
+        'f' => 0x0C,
+        'n' => 0x0A,
+        'r' => 0x0D,
+        't' => 0x09,
+        'v' => 0x0B,
+        '0'..='7' => {
+            let mut value = c.to_digit(8).unwrap();
+            for _ in 0..2 {
+                match iter.next_if(|c| c.is_digit(8)) {
+                    Some(digit) => value = value * 8 + digit.to_digit(8).unwrap(),
+                    None => break,
+                }
+            }
+            if value > 0o377 {
+                let kind = WarningKind::InvalidOctalEscapeSequence(iter.slice_from(offset).into());
+                warnings.push(TokenizeWarning::new(kind, start, iter.pos()));
+            }
+            value
+        }
+        'x' => collect_hex_digits(iter, c, 2, start)?,
+        'u' | 'U' if !bytes => {
+            let value = collect_hex_digits(iter, c, if c == 'u' { 4 } else { 8 }, start)?;
+            if value > 0x10FFFF {
+                let kind = ErrorKind::IllegalUnicodeCharacter;
+                return Err(TokenizeError::new(kind, start, iter.pos()));
+            }
+            value
+        }
+        'N' if !bytes => {
+            let mut name_offset = None;
+            if iter.next_if(|c| *c == '{').is_some() {
+                name_offset = Some(iter.offset());
+                while iter.next_if(|c| !matches!(c, '}' | '\n' | '\r')).is_some() {}
+            }
+            let name = name_offset.map(|name_offset| iter.slice_from(name_offset));
+            return match name {
+                Some(name) if !name.is_empty() && iter.next_if(|c| *c == '}').is_some() => {
+                    Ok(Escape::Named(name))
+                }
+                _ => {
+                    let kind = ErrorKind::MalformedNamedEscape;
+                    Err(TokenizeError::new(kind, start, iter.pos()))
+                }
+            };
+        }
+        _ => {
+            let kind = WarningKind::InvalidEscapeSequence(iter.slice_from(offset).into());
+            warnings.push(TokenizeWarning::new(kind, start, iter.pos()));
+            return Ok(Escape::Unknown);
+        }
+    };
+    Ok(Escape::Value(value))
+}
+
+/// Collects the hexadecimal digits of an escape sequence, returning their value.
+fn collect_hex_digits(
+    iter: &mut PeekableCharTracker,
+    escape: char,
+    count: usize,
+    start: (usize, usize),
+) -> Result<u32> {
+    let mut value = 0;
+    for _ in 0..count {
+        match iter.next_if(|c| c.is_ascii_hexdigit()) {
+            Some(digit) => value = value * 16 + digit.to_digit(16).unwrap(),
+            None => {
+                let kind = ErrorKind::TruncatedEscape(escape);
+                return Err(TokenizeError::new(kind, start, iter.pos()));
+            }
+        }
+    }
+    Ok(value)
 }
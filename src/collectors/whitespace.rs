@@ -0,0 +1,16 @@
+use crate::privat::PeekableCharTracker;
+
+/// Collects spaces, tabs and form feeds.
+///
+/// # Arguments
+///
+/// * `iter` - A mutable reference to a `PeekableCharTracker` instance.
+///
+/// # Returns
+///
+/// * `&str` - The collected whitespace, which may be empty.
+pub fn collect_whitespace<'a>(iter: &mut PeekableCharTracker<'a>) -> &'a str {
+    let start = iter.offset();
+    while iter.next_if(|c| matches!(c, ' ' | '\t' | '\x0c')).is_some() {}
+    iter.slice_from(start)
+}
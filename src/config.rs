@@ -0,0 +1,145 @@
+use std::fmt::{Display, Formatter};
+
+use crate::{ErrorKind, Result, TokenizeError};
+
+/// A version of Python, whose lexical grammar the tokenizer follows.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::PythonVersion;
+///
+/// assert!(PythonVersion::new(3, 8) < PythonVersion::new(3, 12));
+/// assert_eq!(PythonVersion::new(3, 12).to_string(), "3.12");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PythonVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl PythonVersion {
+    /// The newest version of Python the tokenizer knows of.
+    pub const LATEST: PythonVersion = PythonVersion::new(3, 14);
+
+    /// Creates a version from its major and minor numbers.
+    pub const fn new(major: u8, minor: u8) -> Self {
+        PythonVersion { major, minor }
+    }
+}
+
+impl Default for PythonVersion {
+    fn default() -> Self {
+        PythonVersion::LATEST
+    }
+}
+
+impl Display for PythonVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// A piece of the lexical grammar that only some versions of Python have.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// Underscores between the digits of numbers, such as `1_000`, since Python 3.6.
+    NumericUnderscores,
+    /// Formatted string literals, such as `f"{x}"`, since Python 3.6.
+    FStrings,
+    /// The `:=` operator of assignment expressions, since Python 3.8.
+    AssignmentExpressions,
+    /// F-strings split into `FSTRING_START`, `FSTRING_MIDDLE` and `FSTRING_END`
+    /// tokens by PEP 701, since Python 3.12. Before it an f-string is a single string token.
+    FStringTokens,
+    /// Template string literals, such as `t"{x}"`, by PEP 750 since Python 3.14.
+    TemplateStrings,
+}
+
+impl Feature {
+    /// Returns the first version of Python that has the feature.
+    pub fn since(self) -> PythonVersion {
+        match self {
+            Feature::NumericUnderscores | Feature::FStrings => PythonVersion::new(3, 6),
+            Feature::AssignmentExpressions => PythonVersion::new(3, 8),
+            Feature::FStringTokens => PythonVersion::new(3, 12),
+            Feature::TemplateStrings => PythonVersion::new(3, 14),
+        }
+    }
+}
+
+impl Display for Feature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Feature::NumericUnderscores => write!(f, "underscores in numeric literals"),
+            Feature::FStrings => write!(f, "f-strings"),
+            Feature::AssignmentExpressions => write!(f, "assignment expressions"),
+            Feature::FStringTokens => write!(f, "f-string tokens"),
+            Feature::TemplateStrings => write!(f, "t-strings"),
+        }
+    }
+}
+
+/// The settings of a [`Tokenizer`](crate::Tokenizer).
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{ErrorKind, Feature, PythonVersion, Token, Tokenizer, TokenizerConfig};
+///
+/// let config = TokenizerConfig {
+///     version: PythonVersion::new(3, 7),
+///     ..TokenizerConfig::default()
+/// };
+/// let err = Tokenizer::with_config("if (n := 10) > 5:\n", config)
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap_err();
+///
+/// assert_eq!(
+///     err.kind,
+///     ErrorKind::UnsupportedFeature {
+///         feature: Feature::AssignmentExpressions,
+///         version: PythonVersion::new(3, 7),
+///     }
+/// );
+///
+/// let tokens = Tokenizer::with_config("f'{x}'", config).collect::<Result<Vec<_>, _>>();
+/// assert_eq!(tokens.unwrap()[0], Token::String("f'{x}'".to_string()));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TokenizerConfig {
+    /// The version of Python whose grammar is followed,
+    /// the [latest](PythonVersion::LATEST) one by default.
+    pub version: PythonVersion,
+    /// Whether whitespace and continuations are kept as tokens,
+    /// see [`Tokenizer::lossless`](crate::Tokenizer::lossless).
+    pub lossless: bool,
+    /// Whether errors are recorded instead of stopping the tokenizer,
+    /// see [`Tokenizer::recover`](crate::Tokenizer::recover).
+    pub recover: bool,
+}
+
+impl TokenizerConfig {
+    /// Checks if the target version of Python has the feature.
+    pub fn supports(&self, feature: Feature) -> bool {
+        self.version >= feature.since()
+    }
+
+    /// Fails with an error spanning from `start` up to `end`
+    /// if the target version of Python does not have the feature.
+    pub(crate) fn require(
+        &self,
+        feature: Feature,
+        start: (usize, usize),
+        end: (usize, usize),
+    ) -> Result<()> {
+        if self.supports(feature) {
+            return Ok(());
+        }
+        let kind = ErrorKind::UnsupportedFeature {
+            feature,
+            version: self.version,
+        };
+        Err(TokenizeError::new(kind, start, end))
+    }
+}
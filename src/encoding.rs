@@ -0,0 +1,272 @@
+use crate::privat::lines;
+use crate::{ErrorKind, Result, Token, TokenInfo, TokenizeError, Tokenizer, TokenizerConfig};
+
+/// The byte order mark that starts some UTF-8 files.
+const BOM_UTF8: &[u8] = b"\xef\xbb\xbf";
+
+/// Characters of the Windows-1252 code page for the bytes `0x80..=0x9F`,
+/// where it differs from Latin-1. Bytes without a character are `None`.
+const CP1252_HIGH: [Option<char>; 32] = [
+    Some('\u{20AC}'),
+    None,
+    Some('\u{201A}'),
+    Some('\u{0192}'),
+    Some('\u{201E}'),
+    Some('\u{2026}'),
+    Some('\u{2020}'),
+    Some('\u{2021}'),
+    Some('\u{02C6}'),
+    Some('\u{2030}'),
+    Some('\u{0160}'),
+    Some('\u{2039}'),
+    Some('\u{0152}'),
+    None,
+    Some('\u{017D}'),
+    None,
+    None,
+    Some('\u{2018}'),
+    Some('\u{2019}'),
+    Some('\u{201C}'),
+    Some('\u{201D}'),
+    Some('\u{2022}'),
+    Some('\u{2013}'),
+    Some('\u{2014}'),
+    Some('\u{02DC}'),
+    Some('\u{2122}'),
+    Some('\u{0161}'),
+    Some('\u{203A}'),
+    Some('\u{0153}'),
+    None,
+    Some('\u{017E}'),
+    Some('\u{0178}'),
+];
+
+/// The encodings a source can be decoded from.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Codec {
+    Utf8,
+    Latin1,
+    Cp1252,
+    Ascii,
+}
+
+impl Codec {
+    /// Looks up a codec by an encoding name, ignoring case and `-` or `_`.
+    fn lookup(name: &str) -> Option<Codec> {
+        let name = name.to_ascii_lowercase().replace('_', "-");
+        match name.as_str() {
+            "utf-8" | "utf8" | "utf-8-sig" => Some(Codec::Utf8),
+            "iso-8859-1" | "iso8859-1" | "latin-1" | "latin1" | "l1" => Some(Codec::Latin1),
+            "cp1252" | "windows-1252" => Some(Codec::Cp1252),
+            "ascii" | "us-ascii" => Some(Codec::Ascii),
+            _ => None,
+        }
+    }
+
+    /// Decodes the source, reporting the position of the first byte that can not be decoded.
+    fn decode(self, name: &str, source: &[u8]) -> Result<String> {
+        let undecodable = |index: usize| {
+            // The text before the byte is valid, and the other codecs decode a byte to a character
+            let before = match self {
+                Codec::Utf8 => std::str::from_utf8(&source[..index]).unwrap().to_owned(),
+                _ => source[..index].iter().map(|b| *b as char).collect(),
+            };
+            let (line, last) = lines(&before).enumerate().last().unwrap();
+            let kind = ErrorKind::UndecodableByte {
+                encoding: name.to_owned(),
+                byte: source[index],
+            };
+            TokenizeError::at(kind, (line + 1, last.chars().count() + 1))
+        };
+        match self {
+            Codec::Utf8 => match std::str::from_utf8(source) {
+                Ok(text) => Ok(text.to_owned()),
+                Err(err) => Err(undecodable(err.valid_up_to())),
+            },
+            Codec::Latin1 => Ok(source.iter().map(|b| *b as char).collect()),
+            Codec::Cp1252 => source
+                .iter()
+                .enumerate()
+                .map(|(i, b)| match b {
+                    0x80..=0x9f => CP1252_HIGH[(b - 0x80) as usize].ok_or_else(|| undecodable(i)),
+                    _ => Ok(*b as char),
+                })
+                .collect(),
+            Codec::Ascii => match source.iter().position(|b| !b.is_ascii()) {
+                Some(i) => Err(undecodable(i)),
+                None => Ok(source.iter().map(|b| *b as char).collect()),
+            },
+        }
+    }
+}
+
+/// Normalizes the common spellings of UTF-8 and Latin-1 the way CPython does.
+fn normal_name(encoding: &str) -> &str {
+    let enc = encoding.chars().take(12).collect::<String>().to_ascii_lowercase().replace('_', "-");
+    if enc == "utf-8" || enc.starts_with("utf-8-") {
+        "utf-8"
+    } else if ["latin-1", "iso-8859-1", "iso-latin-1"]
+        .iter()
+        .any(|name| enc == *name || enc.starts_with(&format!("{}-", name)))
+    {
+        "iso-8859-1"
+    } else {
+        encoding
+    }
+}
+
+/// Finds an encoding declaration like `# -*- coding: latin-1 -*-` in a line.
+fn find_cookie(line: &[u8]) -> Option<&str> {
+    let line = line.iter().position(|b| !b" \t\x0c".contains(b)).map_or(&line[..0], |i| &line[i..]);
+    if line.first() != Some(&b'#') {
+        return None;
+    }
+    let mut rest = line;
+    while let Some(i) = rest.windows(6).position(|w| w == b"coding") {
+        rest = &rest[i + 6..];
+        if let Some(b':' | b'=') = rest.first() {
+            let value = &rest[1..];
+            let value = &value[value.iter().take_while(|b| **b == b' ' || **b == b'\t').count()..];
+            let len = value
+                .iter()
+                .take_while(|b| b.is_ascii_alphanumeric() || b"-_.".contains(b))
+                .count();
+            if len > 0 {
+                return std::str::from_utf8(&value[..len]).ok();
+            }
+        }
+    }
+    None
+}
+
+/// Checks if a line is blank or holds only a comment.
+fn is_blank(line: &[u8]) -> bool {
+    matches!(
+        line.iter().find(|b| !b" \t\x0c".contains(b)),
+        None | Some(b'#' | b'\r' | b'\n')
+    )
+}
+
+/// Detects the encoding of a Python source, like Python's `tokenize.detect_encoding`.
+///
+/// The encoding is taken from a UTF-8 byte order mark or from a PEP 263
+/// encoding declaration in one of the first two lines, and defaults to `utf-8`.
+/// A source starting with a byte order mark is reported as `utf-8-sig`.
+///
+/// # Errors
+///
+/// * `ErrorKind::UnknownEncoding` - If the declared encoding is unknown.
+/// * `ErrorKind::EncodingProblem` - If the declared encoding does not agree
+///   with the byte order mark.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::detect_encoding;
+///
+/// assert_eq!(detect_encoding(b"x = 1\n").unwrap(), "utf-8");
+/// assert_eq!(detect_encoding(b"#!/usr/bin/python\n# -*- coding: latin-1 -*-\n").unwrap(), "iso-8859-1");
+/// assert_eq!(detect_encoding(b"\xef\xbb\xbfx = 1\n").unwrap(), "utf-8-sig");
+/// assert!(detect_encoding(b"\xef\xbb\xbf# coding: latin-1\n").is_err());
+/// ```
+pub fn detect_encoding(source: &[u8]) -> Result<String> {
+    let bom = source.starts_with(BOM_UTF8);
+    let source = if bom { &source[BOM_UTF8.len()..] } else { source };
+    let mut lines = source.split_inclusive(|b| *b == b'\n');
+
+    let mut cookie = None;
+    if let Some(first) = lines.next() {
+        cookie = find_cookie(first).map(|cookie| (cookie, 1));
+        if cookie.is_none() && is_blank(first) {
+            cookie = lines.next().and_then(find_cookie).map(|cookie| (cookie, 2));
+        }
+    }
+
+    match cookie {
+        Some((cookie, line)) => {
+            let encoding = normal_name(cookie);
+            if Codec::lookup(encoding).is_none() {
+                let kind = ErrorKind::UnknownEncoding(encoding.to_owned());
+                return Err(TokenizeError::at(kind, (line, 1)));
+            }
+            if bom {
+                // The byte order mark allows no encoding other than UTF-8
+                if encoding != "utf-8" {
+                    let kind = ErrorKind::EncodingProblem("utf-8".to_owned());
+                    return Err(TokenizeError::at(kind, (line, 1)));
+                }
+                return Ok("utf-8-sig".to_owned());
+            }
+            Ok(encoding.to_owned())
+        }
+        None if bom => Ok("utf-8-sig".to_owned()),
+        None => Ok("utf-8".to_owned()),
+    }
+}
+
+/// Tokenizes the bytes of a Python source, like Python's `tokenize.tokenize`.
+///
+/// The source is decoded with the encoding found by [`detect_encoding`],
+/// and the first token is always a `Token::Encoding` naming that encoding,
+/// which is `utf-8-sig` for a source starting with a UTF-8 byte order mark.
+///
+/// # Errors
+///
+/// * `ErrorKind::UndecodableByte` - If the source can not be decoded.
+/// * For the same reasons as [`detect_encoding`] and [`tokenize`](crate::tokenize).
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{tokenize_bytes, Token};
+///
+/// let tokens = tokenize_bytes(b"# coding: latin-1\ns = '\xe9t\xe9'\n").unwrap();
+///
+/// assert_eq!(tokens[0].token, Token::Encoding("iso-8859-1".to_string()));
+/// assert_eq!(tokens[5].token, Token::String("'\u{e9}t\u{e9}'".to_string()));
+/// ```
+pub fn tokenize_bytes(source: &[u8]) -> Result<Vec<TokenInfo>> {
+    tokenize_bytes_with_config(source, TokenizerConfig::default())
+}
+
+/// Tokenizes the bytes of a Python source with the given settings, like [`tokenize_bytes`].
+///
+/// The first error is returned, so the `recover` setting is left out.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{tokenize_bytes_with_config, Feature, PythonVersion, TokenizerConfig};
+///
+/// let config = TokenizerConfig {
+///     version: PythonVersion::new(3, 5),
+///     ..TokenizerConfig::default()
+/// };
+/// let err = tokenize_bytes_with_config(b"x = 1_000\n", config).unwrap_err();
+///
+/// assert_eq!(err.start, (1, 5));
+/// ```
+pub fn tokenize_bytes_with_config(
+    source: &[u8],
+    config: TokenizerConfig,
+) -> Result<Vec<TokenInfo>> {
+    let encoding = detect_encoding(source)?;
+    let source = source.strip_prefix(BOM_UTF8).unwrap_or(source);
+    let text = Codec::lookup(&encoding).unwrap().decode(&encoding, source)?;
+
+    let mut tokens = vec![TokenInfo {
+        token: Token::Encoding(encoding),
+        start: (0, 0),
+        end: (0, 0),
+        line: String::new(),
+    }];
+    let config = TokenizerConfig {
+        recover: false,
+        ..config
+    };
+    let mut tokenizer = Tokenizer::with_config(&text, config);
+    while let Some(info) = tokenizer.next_info() {
+        tokens.push(info?);
+    }
+    Ok(tokens)
+}
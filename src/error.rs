@@ -1,70 +1,324 @@
 use std::error::Error;
-use std::fmt::{Debug, Display, Formatter};
+use std::fmt::{Display, Formatter};
 
-/// An enumeration of possible errors that can occur during tokenization.
-///
-/// # Examples
-///
-/// ```
-/// use tokenizer_py::{tokenize, Token, TokenizeError};
-///
+use crate::{Feature, PythonVersion};
+
+/// The kinds of errors that can occur during tokenization.
 ///
-/// assert_eq!(
-///     Err(TokenizeError::Number("Invalid decimal literal".to_owned(), (1, 3))), 
-///     tokenize("1_.1")
-/// );
-/// ```
-#[derive(PartialEq, Eq)]
-pub enum TokenizeError {
-    EscapeSeq(String, (usize, usize)),
-    String(String, (usize, usize)),
-    Number(String, (usize, usize)),
-    Operator(String, (usize, usize)),
-    Char(String, (usize, usize)),
-    Indent(String, (usize, usize)),
-    EndOfFile(String, (usize, usize)),
+/// Each kind has a stable [code](ErrorKind::code) and a message
+/// that is generated from it and its data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A single-quoted string that is not closed before the end of its line.
+    UnterminatedString,
+    /// A triple-quoted string that is not closed before the end of the text.
+    UnterminatedTripleQuotedString,
+    /// A single-quoted f-string that is not closed before the end of its line.
+    UnterminatedFString,
+    /// A triple-quoted f-string that is not closed before the end of the text.
+    UnterminatedTripleQuotedFString,
+    /// A single-quoted t-string that is not closed before the end of its line.
+    UnterminatedTString,
+    /// A triple-quoted t-string that is not closed before the end of the text.
+    UnterminatedTripleQuotedTString,
+    /// A string prefix that Python does not allow.
+    InvalidStringPrefix(String),
+    /// A `\x`, `\u` or `\U` escape sequence with too few hexadecimal digits.
+    TruncatedEscape(char),
+    /// A `\N{...}` escape sequence without braces or without a name.
+    MalformedNamedEscape,
+    /// A `\U` escape sequence above the largest Unicode character.
+    IllegalUnicodeCharacter,
+    /// A character outside of ASCII in a bytes literal.
+    NonAsciiBytes,
+    /// An f-string or a t-string whose value is asked for, which only exists at run time.
+    FStringValue,
+    /// Adjacent string literals that are neither all bytes nor all strings.
+    MixedBytesAndStr,
+    /// A `\N{...}` escape sequence naming no Unicode character.
+    UnknownCharacterName(String),
+    /// An escape sequence for a surrogate, which a Rust `String` can not hold.
+    SurrogateCharacter(u32),
+    /// A `}` in the literal text of an f-string that is not doubled as `}}`.
+    SingleClosingBrace,
+    /// An f-string that ends before the `}` of a replacement field, as in `f'{x:>10'`.
+    ExpectingClosingBrace,
+    /// A malformed decimal number, such as `1_` or `1e+`.
+    InvalidDecimalLiteral,
+    /// A hexadecimal integer without digits or followed by a letter, such as `0x` or `0x1g`.
+    InvalidHexadecimalLiteral,
+    /// An octal integer without digits or followed by a letter, such as `0o` or `0o1a`.
+    InvalidOctalLiteral,
+    /// A binary integer without digits or followed by a letter, such as `0b` or `0b1a`.
+    InvalidBinaryLiteral,
+    /// A digit out of the radix of an integer, such as the `2` of `0b12`.
+    InvalidDigit { digit: char, radix: u32 },
+    /// A decimal integer with leading zeros, such as `0777`.
+    LeadingZeros,
+    /// An imaginary number followed by a letter, such as `1jx`.
+    InvalidImaginaryLiteral,
+    /// An integer whose value does not fit in an `i128`.
+    IntegerOverflow,
+    /// A line indented deeper than the one before it, which does not open a block.
+    UnexpectedIndent,
+    /// A line indented less than the one before it, but not as much as any outer block.
+    UnindentMismatch,
+    /// An indentation whose depth depends on the width of a tab,
+    /// like Python's `TabError`.
+    InconsistentTabs,
+    /// A closing bracket that does not match the innermost open bracket, such as `(1]`.
+    ///
+    /// The message of a [`TokenizeError`] names the line of the open bracket
+    /// when it is not the line of the closing one, as CPython does.
+    MismatchedBracket {
+        opening: char,
+        /// Where the open bracket is.
+        opening_start: (usize, usize),
+        closing: char,
+    },
+    /// A closing bracket with no open bracket before it.
+    UnmatchedBracket(char),
+    /// A bracket still open at the end of the text, which the error points at.
+    UnclosedBracket(char),
+    /// A character that can not appear in Python source outside of strings and comments.
+    InvalidCharacter(char),
+    /// A backslash that is not at the end of a line, outside of strings and comments.
+    UnexpectedCharacterAfterContinuation,
+    /// A line joined with a backslash to a line past the end of the text.
+    UnexpectedEof,
+    /// Syntax that the target version of Python does not have yet.
+    UnsupportedFeature {
+        feature: Feature,
+        version: PythonVersion,
+    },
+    /// An error reading the source.
+    Io(String),
+    /// An encoding declaration naming an encoding that is not supported.
+    UnknownEncoding(String),
+    /// An encoding declaration that disagrees with the byte order mark.
+    EncodingProblem(String),
+    /// A byte that can not be decoded in the encoding of the source.
+    UndecodableByte { encoding: String, byte: u8 },
 }
 
-impl Debug for TokenizeError {
+impl ErrorKind {
+    /// Returns the stable code of the kind of error.
+    ///
+    /// The hundreds of the code tell the group of the error: `E01xx` for strings,
+    /// `E02xx` for numbers, `E03xx` for indentation, `E04xx` for brackets,
+    /// `E05xx` for characters, `E06xx` for the target version
+    /// and `E09xx` for reading the source.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::UnterminatedString => "E0101",
+            ErrorKind::UnterminatedTripleQuotedString => "E0102",
+            ErrorKind::UnterminatedFString => "E0103",
+            ErrorKind::UnterminatedTripleQuotedFString => "E0104",
+            ErrorKind::InvalidStringPrefix(_) => "E0105",
+            ErrorKind::TruncatedEscape(_) => "E0106",
+            ErrorKind::MalformedNamedEscape => "E0107",
+            ErrorKind::IllegalUnicodeCharacter => "E0108",
+            ErrorKind::NonAsciiBytes => "E0109",
+            ErrorKind::FStringValue => "E0110",
+            ErrorKind::MixedBytesAndStr => "E0111",
+            ErrorKind::UnknownCharacterName(_) => "E0112",
+            ErrorKind::SurrogateCharacter(_) => "E0113",
+            ErrorKind::SingleClosingBrace => "E0114",
+            ErrorKind::ExpectingClosingBrace => "E0115",
+            ErrorKind::UnterminatedTString => "E0116",
+            ErrorKind::UnterminatedTripleQuotedTString => "E0117",
+            ErrorKind::InvalidDecimalLiteral => "E0201",
+            ErrorKind::InvalidHexadecimalLiteral => "E0202",
+            ErrorKind::InvalidOctalLiteral => "E0203",
+            ErrorKind::InvalidBinaryLiteral => "E0204",
+            ErrorKind::InvalidDigit { .. } => "E0205",
+            ErrorKind::LeadingZeros => "E0206",
+            ErrorKind::InvalidImaginaryLiteral => "E0207",
+            ErrorKind::IntegerOverflow => "E0208",
+            ErrorKind::UnexpectedIndent => "E0301",
+            ErrorKind::UnindentMismatch => "E0302",
+            ErrorKind::InconsistentTabs => "E0303",
+            ErrorKind::MismatchedBracket { .. } => "E0401",
+            ErrorKind::UnmatchedBracket(_) => "E0402",
+            ErrorKind::UnclosedBracket(_) => "E0403",
+            ErrorKind::InvalidCharacter(_) => "E0501",
+            ErrorKind::UnexpectedCharacterAfterContinuation => "E0502",
+            ErrorKind::UnexpectedEof => "E0503",
+            ErrorKind::UnsupportedFeature { .. } => "E0601",
+            ErrorKind::Io(_) => "E0901",
+            ErrorKind::UnknownEncoding(_) => "E0902",
+            ErrorKind::EncodingProblem(_) => "E0903",
+            ErrorKind::UndecodableByte { .. } => "E0904",
+        }
+    }
+}
+
+impl Display for ErrorKind {
+    /// Writes the message of the error, worded like the errors of CPython.
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        
         match self {
-            TokenizeError::EscapeSeq(desc, pos) => {
-                let (line, character) = pos;
-                write!(f, "Escape Sequence Error: {} at pos {}:{}", desc, line, character)
+            ErrorKind::UnterminatedString => write!(f, "unterminated string literal"),
+            ErrorKind::UnterminatedTripleQuotedString => {
+                write!(f, "unterminated triple-quoted string literal")
+            }
+            ErrorKind::UnterminatedFString => write!(f, "unterminated f-string literal"),
+            ErrorKind::UnterminatedTripleQuotedFString => {
+                write!(f, "unterminated triple-quoted f-string literal")
+            }
+            ErrorKind::UnterminatedTString => write!(f, "unterminated t-string literal"),
+            ErrorKind::UnterminatedTripleQuotedTString => {
+                write!(f, "unterminated triple-quoted t-string literal")
+            }
+            ErrorKind::InvalidStringPrefix(prefix) => {
+                write!(f, "invalid string prefix '{}'", prefix)
+            }
+            ErrorKind::TruncatedEscape(escape) => {
+                let digits = match escape {
+                    'x' => "XX",
+                    'u' => "XXXX",
+                    _ => "XXXXXXXX",
+                };
+                write!(f, "truncated \\{}{} escape", escape, digits)
+            }
+            ErrorKind::MalformedNamedEscape => write!(f, "malformed \\N character escape"),
+            ErrorKind::IllegalUnicodeCharacter => write!(f, "illegal Unicode character"),
+            ErrorKind::NonAsciiBytes => {
+                write!(f, "bytes can only contain ASCII literal characters")
+            }
+            ErrorKind::FStringValue => {
+                write!(f, "f-strings and t-strings do not have a constant value")
+            }
+            ErrorKind::MixedBytesAndStr => write!(f, "cannot mix bytes and nonbytes literals"),
+            ErrorKind::UnknownCharacterName(name) => {
+                write!(f, "unknown Unicode character name '{}'", name)
+            }
+            ErrorKind::SurrogateCharacter(value) => {
+                write!(f, "surrogate character U+{:04X} can not be decoded", value)
+            }
+            ErrorKind::SingleClosingBrace => write!(f, "f-string: single '}}' is not allowed"),
+            ErrorKind::ExpectingClosingBrace => write!(f, "f-string: expecting '}}'"),
+            ErrorKind::InvalidDecimalLiteral => write!(f, "invalid decimal literal"),
+            ErrorKind::InvalidHexadecimalLiteral => write!(f, "invalid hexadecimal literal"),
+            ErrorKind::InvalidOctalLiteral => write!(f, "invalid octal literal"),
+            ErrorKind::InvalidBinaryLiteral => write!(f, "invalid binary literal"),
+            ErrorKind::InvalidDigit { digit, radix } => {
+                let name = if *radix == 2 { "binary" } else { "octal" };
+                write!(f, "invalid digit '{}' in {} literal", digit, name)
             }
-            TokenizeError::String(desc, pos) => {
-                let (line, character) = pos;
-                write!(f, "String Error: {} at pos {}:{}", desc, line, character)
+            ErrorKind::LeadingZeros => write!(
+                f,
+                "leading zeros in decimal integer literals are not permitted; \
+                 use an 0o prefix for octal integers"
+            ),
+            ErrorKind::InvalidImaginaryLiteral => write!(f, "invalid imaginary literal"),
+            ErrorKind::IntegerOverflow => write!(f, "integer literal too large for 128 bits"),
+            ErrorKind::UnexpectedIndent => write!(f, "unexpected indent"),
+            ErrorKind::UnindentMismatch => {
+                write!(f, "unindent does not match any outer indentation level")
             }
-            TokenizeError::Number(desc, pos) => {
-                let (line, character) = pos;
-                write!(f, "Number Error: {} at pos {}:{}", desc, line, character)
+            ErrorKind::InconsistentTabs => {
+                write!(f, "inconsistent use of tabs and spaces in indentation")
             }
-            TokenizeError::Operator(desc, pos) => {
-                let (line, character) = pos;
-                write!(f, "Operator Error: {} at pos {}:{}", desc, line, character)
+            ErrorKind::MismatchedBracket {
+                opening, closing, ..
+            } => write!(
+                f,
+                "closing parenthesis '{}' does not match opening parenthesis '{}'",
+                closing, opening
+            ),
+            ErrorKind::UnmatchedBracket(c) => write!(f, "unmatched '{}'", c),
+            ErrorKind::UnclosedBracket(c) => write!(f, "'{}' was never closed", c),
+            ErrorKind::InvalidCharacter(c) => {
+                write!(f, "invalid character '{}' (U+{:04X})", c, *c as u32)
             }
-            TokenizeError::Char(desc, pos) => {
-                let (line, character) = pos;
-                write!(f, "Character Error: {} at pos {}:{}", desc, line, character)
+            ErrorKind::UnexpectedCharacterAfterContinuation => {
+                write!(f, "unexpected character after line continuation character")
             }
-            TokenizeError::Indent(desc, pos) => {
-                let (line, character) = pos;
-                write!(f, "Indentation Error: {} at pos {}:{}", desc, line, character)
+            ErrorKind::UnexpectedEof => write!(f, "unexpected EOF while parsing"),
+            ErrorKind::UnsupportedFeature { feature, version } => write!(
+                f,
+                "{} are not available before Python {}, the target is Python {}",
+                feature,
+                feature.since(),
+                version
+            ),
+            ErrorKind::Io(err) => write!(f, "{}", err),
+            ErrorKind::UnknownEncoding(encoding) => write!(f, "unknown encoding: {}", encoding),
+            ErrorKind::EncodingProblem(encoding) => write!(f, "encoding problem: {}", encoding),
+            ErrorKind::UndecodableByte { encoding, byte } => {
+                write!(f, "'{}' codec can't decode byte {:#04x}", encoding, byte)
             }
-            TokenizeError::EndOfFile(desc, pos) => {
-                let (line, character) = pos;
-                write!(f, "End of File Error: {} at pos {}:{}", desc, line, character)
+        }
+    }
+}
+
+/// An error that occurred during tokenization.
+///
+/// The error spans the source from `start` up to `end`,
+/// given as a line and a column that both start at 1.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{tokenize, ErrorKind, TokenizeError};
+///
+/// let err = tokenize("1_.1").unwrap_err();
+///
+/// assert_eq!(err, TokenizeError::new(ErrorKind::InvalidDecimalLiteral, (1, 3), (1, 4)));
+/// assert_eq!(err.code(), "E0201");
+/// assert_eq!(err.message(), "invalid decimal literal");
+/// assert_eq!(err.to_string(), "error[E0201]: invalid decimal literal at 1:3");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenizeError {
+    /// The kind of the error.
+    pub kind: ErrorKind,
+    /// The position where the error starts.
+    pub start: (usize, usize),
+    /// The position just past the end of the error.
+    pub end: (usize, usize),
+}
+
+impl TokenizeError {
+    /// Creates an error of the given kind spanning from `start` up to `end`.
+    pub fn new(kind: ErrorKind, start: (usize, usize), end: (usize, usize)) -> Self {
+        TokenizeError { kind, start, end }
+    }
+
+    /// Creates an error of the given kind at the character at `pos`.
+    pub(crate) fn at(kind: ErrorKind, pos: (usize, usize)) -> Self {
+        TokenizeError::new(kind, pos, (pos.0, pos.1 + 1))
+    }
+
+    /// Returns the stable code of the error.
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+
+    /// Returns the message of the error.
+    pub fn message(&self) -> String {
+        match self.kind {
+            ErrorKind::MismatchedBracket { opening_start, .. }
+                if opening_start.0 != self.start.0 =>
+            {
+                format!("{} on line {}", self.kind, opening_start.0)
             }
+            _ => self.kind.to_string(),
         }
     }
 }
 
 impl Display for TokenizeError {
-    #[inline]
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        let (line, column) = self.start;
+        write!(
+            f,
+            "error[{}]: {} at {}:{}",
+            self.code(),
+            self.message(),
+            line,
+            column
+        )
     }
 }
 
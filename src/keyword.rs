@@ -0,0 +1,137 @@
+use crate::PythonVersion;
+
+/// What a name is to the grammar of Python.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NameKind {
+    /// A keyword, which can never be used as an identifier, such as `def` or `lambda`.
+    Keyword,
+    /// A soft keyword, which is a keyword only in some places, such as `match` or `type`.
+    SoftKeyword,
+    /// Any other name, such as a variable or function name.
+    Identifier,
+}
+
+/// The keywords of Python 3 in the order of `keyword.kwlist`,
+/// with the version they came in and the version they were gone in, if any.
+const KEYWORDS: [(&str, PythonVersion, Option<PythonVersion>); 36] = [
+    ("False", PythonVersion::new(3, 0), None),
+    ("None", PythonVersion::new(3, 0), None),
+    ("True", PythonVersion::new(3, 0), None),
+    // Only Python 3.9 has it, to switch to the old parser
+    (
+        "__peg_parser__",
+        PythonVersion::new(3, 9),
+        Some(PythonVersion::new(3, 10)),
+    ),
+    ("and", PythonVersion::new(3, 0), None),
+    ("as", PythonVersion::new(3, 0), None),
+    ("assert", PythonVersion::new(3, 0), None),
+    ("async", PythonVersion::new(3, 7), None),
+    ("await", PythonVersion::new(3, 7), None),
+    ("break", PythonVersion::new(3, 0), None),
+    ("class", PythonVersion::new(3, 0), None),
+    ("continue", PythonVersion::new(3, 0), None),
+    ("def", PythonVersion::new(3, 0), None),
+    ("del", PythonVersion::new(3, 0), None),
+    ("elif", PythonVersion::new(3, 0), None),
+    ("else", PythonVersion::new(3, 0), None),
+    ("except", PythonVersion::new(3, 0), None),
+    ("finally", PythonVersion::new(3, 0), None),
+    ("for", PythonVersion::new(3, 0), None),
+    ("from", PythonVersion::new(3, 0), None),
+    ("global", PythonVersion::new(3, 0), None),
+    ("if", PythonVersion::new(3, 0), None),
+    ("import", PythonVersion::new(3, 0), None),
+    ("in", PythonVersion::new(3, 0), None),
+    ("is", PythonVersion::new(3, 0), None),
+    ("lambda", PythonVersion::new(3, 0), None),
+    ("nonlocal", PythonVersion::new(3, 0), None),
+    ("not", PythonVersion::new(3, 0), None),
+    ("or", PythonVersion::new(3, 0), None),
+    ("pass", PythonVersion::new(3, 0), None),
+    ("raise", PythonVersion::new(3, 0), None),
+    ("return", PythonVersion::new(3, 0), None),
+    ("try", PythonVersion::new(3, 0), None),
+    ("while", PythonVersion::new(3, 0), None),
+    ("with", PythonVersion::new(3, 0), None),
+    ("yield", PythonVersion::new(3, 0), None),
+];
+
+/// The soft keywords in the order of `keyword.softkwlist`, with the version they came in.
+const SOFT_KEYWORDS: [(&str, PythonVersion); 4] = [
+    ("_", PythonVersion::new(3, 10)),
+    ("case", PythonVersion::new(3, 10)),
+    ("match", PythonVersion::new(3, 10)),
+    ("type", PythonVersion::new(3, 12)),
+];
+
+/// Returns the keywords of the given version of Python, like its `keyword.kwlist`.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{keywords, PythonVersion};
+///
+/// assert!(keywords(PythonVersion::LATEST).contains(&"async"));
+/// assert!(!keywords(PythonVersion::new(3, 6)).contains(&"async"));
+/// assert_eq!(keywords(PythonVersion::LATEST).len(), 35);
+/// ```
+pub fn keywords(version: PythonVersion) -> Vec<&'static str> {
+    keywords_of(version).collect()
+}
+
+/// Iterates over the keywords of the given version of Python.
+fn keywords_of(version: PythonVersion) -> impl Iterator<Item = &'static str> {
+    KEYWORDS
+        .iter()
+        .filter(move |(_, since, until)| {
+            version >= *since && until.map_or(true, |until| version < until)
+        })
+        .map(|(keyword, _, _)| *keyword)
+}
+
+/// Returns the soft keywords of the given version of Python, like its `keyword.softkwlist`.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{soft_keywords, PythonVersion};
+///
+/// assert_eq!(soft_keywords(PythonVersion::new(3, 12)), vec!["_", "case", "match", "type"]);
+/// assert!(soft_keywords(PythonVersion::new(3, 9)).is_empty());
+/// ```
+pub fn soft_keywords(version: PythonVersion) -> Vec<&'static str> {
+    soft_keywords_of(version).collect()
+}
+
+/// Iterates over the soft keywords of the given version of Python.
+fn soft_keywords_of(version: PythonVersion) -> impl Iterator<Item = &'static str> {
+    SOFT_KEYWORDS.iter().filter(move |(_, since)| version >= *since).map(|(keyword, _)| *keyword)
+}
+
+/// Tells whether a name is a keyword, a soft keyword or an identifier
+/// in the given version of Python.
+///
+/// Names are compared as written, so `ｄｅｆ`, which Python normalizes to `def`,
+/// is an identifier as it is in Python.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{classify_name, NameKind, PythonVersion};
+///
+/// let version = PythonVersion::LATEST;
+/// assert_eq!(classify_name("lambda", version), NameKind::Keyword);
+/// assert_eq!(classify_name("match", version), NameKind::SoftKeyword);
+/// assert_eq!(classify_name("matches", version), NameKind::Identifier);
+/// assert_eq!(classify_name("match", PythonVersion::new(3, 9)), NameKind::Identifier);
+/// ```
+pub fn classify_name(name: &str, version: PythonVersion) -> NameKind {
+    if keywords_of(version).any(|keyword| keyword == name) {
+        NameKind::Keyword
+    } else if soft_keywords_of(version).any(|keyword| keyword == name) {
+        NameKind::SoftKeyword
+    } else {
+        NameKind::Identifier
+    }
+}
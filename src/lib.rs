@@ -1,23 +1,44 @@
-use collectors::{
-    collect_comment, collect_fstring, collect_indent, collect_name, collect_number,
-    collect_operator, collect_string,
+pub use config::{Feature, PythonVersion, TokenizerConfig};
+pub use encoding::{detect_encoding, tokenize_bytes, tokenize_bytes_with_config};
+pub use error::{ErrorKind, TokenizeError};
+pub use keyword::{classify_name, keywords, soft_keywords, NameKind};
+pub use literal::{decode_number, decode_string, decode_strings, NumberValue, StringValue};
+pub use operator::OperatorKind;
+pub use readline::{
+    generate_tokens, generate_tokens_with_config, tokenize_reader, tokenize_reader_with_config,
+    GenerateTokens,
 };
-pub use error::TokenizeError;
+pub use report::Report;
 
-use privat::PeekableCharTracker;
-pub use token::Token;
+pub use token::{Token, TokenInfo, TokenRef};
+pub use tokenizer::Tokenizer;
+pub use untokenize::{untokenize, untokenize_compat};
+pub use warning::{TokenizeWarning, WarningKind};
 
 mod collectors;
+mod config;
+mod encoding;
 mod error;
+mod keyword;
+mod literal;
+mod operator;
 mod privat;
+mod readline;
+mod report;
 
 #[cfg(test)]
 mod tests;
 mod token;
+mod tokenizer;
+mod untokenize;
+mod warning;
 
 /// The constant `OPERATORS` contains a string that lists
 /// all possible operators that can be used in expressions.
-pub const OPERATORS: &str = "=+-*/%&|<>!^:;.,()[]{}@$?~`";
+///
+/// Every operator of Python starts with one of these characters,
+/// see [`OperatorKind`] for the operators themselves.
+pub const OPERATORS: &str = "=+-*/%&|<>!^:;.,()[]{}@~";
 
 /// An alias of type `Result<T>` for a standard Rust
 /// result with a possible error of type `TokenizeError`.
@@ -41,97 +62,154 @@ pub type Result<T> = std::result::Result<T, TokenizeError>;
 /// ]);
 /// ```
 pub fn tokenize(text: impl ToString) -> Result<Vec<Token>> {
-    let mut tokens: Vec<Token> = Vec::new();
-    let mut text = text.to_string();
+    Tokenizer::new(&text.to_string()).collect()
+}
 
-    if !text.ends_with('\n') {
-        text.push('\n')
-    }
+/// Tokenizes the whole text, collecting every error instead of stopping at the first one.
+///
+/// The text that could not be tokenized is returned as `Token::ErrorToken` tokens,
+/// as in the [error recovery mode](Tokenizer::recover) of the `Tokenizer`,
+/// and the errors come in the order they were found.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{Token, tokenize_with_errors};
+///
+/// let (tokens, errors) = tokenize_with_errors("a = 'x\nb = \"y\nc = 'z'\n");
+///
+/// assert_eq!(errors.len(), 2);
+/// assert_eq!(tokens[2], Token::ErrorToken("'x".to_string()));
+/// assert_eq!(tokens[6], Token::ErrorToken("\"y".to_string()));
+/// assert_eq!(tokens[10], Token::String("'z'".to_string()));
+/// ```
+pub fn tokenize_with_errors(text: impl ToString) -> (Vec<Token>, Vec<TokenizeError>) {
+    let text = text.to_string();
+    let mut tokenizer = Tokenizer::new(&text).recover(true);
+    let tokens = tokenizer.by_ref().filter_map(|token| token.ok()).collect();
+    (tokens, tokenizer.into_errors())
+}
 
-    let mut iter = PeekableCharTracker::new(text.chars().peekable());
-    // A wrapper for Peekable<Chars>
-    // having a tuple: (usize, usize)
-    // to specify a position in the text
+/// Tokenizes the text, keeping the location of every token.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{Token, TokenInfo, tokenize_info};
+///
+/// let tokens = tokenize_info("else:\n  world\n").unwrap();
+///
+/// assert_eq!(tokens[3], TokenInfo {
+///     token: Token::Indent("  ".to_string()),
+///     start: (2, 0),
+///     end: (2, 2),
+///     line: "  world\n".to_string(),
+/// });
+/// assert_eq!(tokens[4].start, (2, 2));
+/// assert_eq!(tokens[4].end, (2, 7));
+/// ```
+pub fn tokenize_info(text: impl ToString) -> Result<Vec<TokenInfo>> {
+    tokenize_info_with_config(text, TokenizerConfig::default())
+}
 
-    let mut ind_stack = vec!["".to_owned()];
-    // A stack of indentation sizes,
-    // the initial zero will be retained until the end of the function
-    let mut brackets_stack = Vec::new();
+/// Tokenizes the text with the given settings, keeping the location of every token.
+///
+/// The first error is returned, so the `recover` setting is left out.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{tokenize_info_with_config, PythonVersion, TokenizerConfig};
+///
+/// let config = TokenizerConfig {
+///     version: PythonVersion::new(3, 7),
+///     ..TokenizerConfig::default()
+/// };
+///
+/// assert!(tokenize_info_with_config("(n := 1)\n", config).is_err());
+/// ```
+pub fn tokenize_info_with_config(
+    text: impl ToString,
+    config: TokenizerConfig,
+) -> Result<Vec<TokenInfo>> {
+    let text = text.to_string();
+    let config = TokenizerConfig {
+        recover: false,
+        ..config
+    };
+    let mut tokenizer = Tokenizer::with_config(&text, config);
+    std::iter::from_fn(|| tokenizer.next_info()).collect()
+}
 
-    while let Some(c) = iter.peek() {
-        match *c {
-            'r' | 'f' | 'b' | 'u' | 'R' | 'F' | 'B' | 'U' => {
-                let c = iter.next();
-                // collecting a prefix
-                match (c, iter.peek()) {
-                    (Some('f' | 'F'), Some('\'' | '"')) => collect_fstring(&mut iter, &mut tokens, c.unwrap())?,
-                    (Some('r' | 'R' | 'b' | 'B' | 'u' | 'U'), Some('\'' | '"')) => {
-                        tokens.push(Token::String(collect_string(&mut iter, c)?));
-                    }
-                    (c, _) => {
-                        tokens.push(Token::Name(collect_name(&mut iter, c)));
-                    }
-                }
-            }
-            '\'' | '"' => {
-                tokens.push(Token::String(collect_string(&mut iter, None)?));
-            }
-            '0'..='9' => tokens.push(Token::Number(collect_number(&mut iter, None)?)),
-            '\n' => {
-                if iter.is_start_of_line() || !brackets_stack.is_empty() {
-                    iter.next();
-                    tokens.push(Token::NL);
-                } else {
-                    iter.next();
-                    tokens.push(Token::NewLine);
-                    let new_ind = collect_indent(&mut iter);
-                    let last_ind = ind_stack.last().unwrap();
-                    if new_ind.len() > last_ind.len() {
-                        ind_stack.push(new_ind.clone());
-                        tokens.push(Token::Indent(new_ind.clone()));
-                    }
-                    while new_ind.len() < ind_stack.last().unwrap().len() {
-                        ind_stack.pop();
-                        tokens.push(Token::Dedent);
-                    }
-                }
-            }
-            '#' => tokens.push(Token::Comment(collect_comment(&mut iter))),
-            c if OPERATORS.contains(c) => {
-                let operator = iter.next().unwrap();
-                match operator {
-                    '[' | '{' | '(' => brackets_stack.push(operator),
-                    ']' if brackets_stack.last() == Some(&'[') => {
-                        brackets_stack.pop();
-                    }
-                    '}' if brackets_stack.last() == Some(&'{') => {
-                        brackets_stack.pop();
-                    }
-                    ')' if brackets_stack.last() == Some(&'(') => {
-                        brackets_stack.pop();
-                    }
-                    '.' => {
-                        if let Some('0'..='9') = iter.peek() {
-                            tokens.push(Token::Number(collect_number(&mut iter, Some(operator))?));
-                            continue;
-                        }
-                    }
-                    _ => {}
-                }
-                tokens.push(Token::OP(collect_operator(&mut iter, operator)?));
-            }
-            c if c.is_alphabetic() || c == '_' => {
-                tokens.push(Token::Name(collect_name(&mut iter, None)));
-            }
-            _ => {
-                iter.next();
-            }
-        };
-    }
-    while !ind_stack.last().unwrap().is_empty() {
-        ind_stack.pop();
-        tokens.push(Token::Dedent);
-    }
-    tokens.push(Token::EndMarker);
-    Ok(tokens)
+/// Tokenizes the text without copying it, borrowing every token from the text.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{TokenRef, tokenize_ref};
+///
+/// let tokens = tokenize_ref("hello world").unwrap();
+///
+/// assert_eq!(tokens, vec![
+///     TokenRef::Name("hello"),
+///     TokenRef::Name("world"),
+///     TokenRef::NewLine(""),
+///     TokenRef::EndMarker,
+/// ]);
+/// ```
+pub fn tokenize_ref(text: &str) -> Result<Vec<TokenRef<'_>>> {
+    tokenize_ref_with_config(text, TokenizerConfig::default())
+}
+
+/// Tokenizes the text with the given settings, borrowing every token from the text.
+///
+/// The first error is returned, so the `recover` setting is left out.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{tokenize_ref_with_config, PythonVersion, TokenRef, TokenizerConfig};
+///
+/// let config = TokenizerConfig {
+///     version: PythonVersion::new(3, 11),
+///     ..TokenizerConfig::default()
+/// };
+/// let tokens = tokenize_ref_with_config("f'{x}'", config).unwrap();
+///
+/// assert_eq!(tokens[0], TokenRef::String("f'{x}'"));
+/// ```
+pub fn tokenize_ref_with_config(text: &str, config: TokenizerConfig) -> Result<Vec<TokenRef<'_>>> {
+    let config = TokenizerConfig {
+        recover: false,
+        ..config
+    };
+    let mut tokenizer = Tokenizer::with_config(text, config);
+    std::iter::from_fn(|| tokenizer.next_ref()).collect()
+}
+
+/// Tokenizes the text in [lossless mode](Tokenizer::lossless), borrowing every token from the text.
+///
+/// Unlike owned tokens, the borrowed ones keep the newline characters they stand for,
+/// so their texts add up to the text.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{TokenRef, tokenize_lossless};
+///
+/// let tokens = tokenize_lossless("x = 1\r\n").unwrap();
+///
+/// assert_eq!(tokens, vec![
+///     TokenRef::Name("x"),
+///     TokenRef::Whitespace(" "),
+///     TokenRef::OP("="),
+///     TokenRef::Whitespace(" "),
+///     TokenRef::Number("1"),
+///     TokenRef::NewLine("\r\n"),
+///     TokenRef::EndMarker,
+/// ]);
+/// ```
+pub fn tokenize_lossless(text: &str) -> Result<Vec<TokenRef<'_>>> {
+    let mut tokenizer = Tokenizer::new(text).lossless(true);
+    std::iter::from_fn(|| tokenizer.next_ref()).collect()
 }
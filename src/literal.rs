@@ -0,0 +1,296 @@
+use crate::collectors::{collect_escape, collect_number, collect_string, Escape};
+use crate::{privat::PeekableCharTracker, ErrorKind, Result, TokenizeError};
+
+/// The value a string literal stands for at run time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StringValue {
+    /// The value of a string literal, such as `'abc'`.
+    Str(String),
+    /// The value of a bytes literal, such as `b'abc'`.
+    Bytes(Vec<u8>),
+}
+
+impl StringValue {
+    /// Appends a character, which is ASCII in a bytes literal.
+    fn push(&mut self, c: char) {
+        match self {
+            StringValue::Str(text) => text.push(c),
+            StringValue::Bytes(data) => data.push(c as u8),
+        }
+    }
+
+    /// Appends a byte to a bytes literal.
+    fn push_byte(&mut self, byte: u8) {
+        if let StringValue::Bytes(data) = self {
+            data.push(byte);
+        }
+    }
+
+    /// Appends a piece of the source, which is ASCII in a bytes literal.
+    fn push_str(&mut self, s: &str) {
+        match self {
+            StringValue::Str(text) => text.push_str(s),
+            StringValue::Bytes(data) => data.extend_from_slice(s.as_bytes()),
+        }
+    }
+}
+
+/// Decodes a string literal, such as the text of a `Token::String`, into its value.
+///
+/// The prefix of the literal tells whether the value is a string or bytes.
+/// Escape sequences are replaced with what they stand for unless the literal is raw,
+/// and line endings inside the literal become `\n`, as Python reads its source.
+///
+/// # Errors
+///
+/// * `ErrorKind::FStringValue` - If the literal is an f-string or a t-string,
+///   whose value is only known at run time.
+/// * `ErrorKind::UnknownCharacterName` - If a `\N{...}` escape names no character,
+///   by its name or by one of its aliases.
+/// * `ErrorKind::SurrogateCharacter` - If an escape sequence stands for a surrogate.
+/// * `ErrorKind::InvalidCharacter` - If the literal is followed by more text,
+///   pointing at its first character.
+/// * For the same reasons as the tokenizer, if the literal is malformed.
+///
+/// The positions of the errors are counted from the start of the literal.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{decode_string, StringValue};
+///
+/// assert_eq!(
+///     decode_string(r"b'\x00abc'").unwrap(),
+///     StringValue::Bytes(b"\x00abc".to_vec())
+/// );
+/// assert_eq!(
+///     decode_string(r"'caf\xe9\N{BLACK STAR}'").unwrap(),
+///     StringValue::Str("café★".to_string())
+/// );
+/// assert_eq!(
+///     decode_string(r"r'\d+'").unwrap(),
+///     StringValue::Str(r"\d+".to_string())
+/// );
+/// ```
+pub fn decode_string(literal: &str) -> Result<StringValue> {
+    let prefix_len = literal.find(['\'', '"']).unwrap_or(literal.len());
+    let prefix = &literal[..prefix_len];
+    if prefix.contains(['f', 'F', 't', 'T']) {
+        return Err(TokenizeError::new(
+            ErrorKind::FStringValue,
+            (1, 1),
+            end_of(literal),
+        ));
+    }
+    if prefix_len == literal.len() {
+        let kind = ErrorKind::UnterminatedString;
+        return Err(TokenizeError::new(kind, (1, 1), end_of(literal)));
+    }
+
+    let mut iter = PeekableCharTracker::new(literal);
+    for _ in prefix.chars() {
+        iter.next();
+    }
+    // The literal is checked as the tokenizer does, so that decoding it can not fail halfway
+    let mut warnings = Vec::new();
+    let mut checked = iter.clone();
+    collect_string(&mut checked, 0, &mut warnings)?;
+    if let Some(&c) = checked.peek() {
+        return Err(TokenizeError::at(
+            ErrorKind::InvalidCharacter(c),
+            checked.pos(),
+        ));
+    }
+
+    let raw = prefix.contains(['r', 'R']);
+    let mut value = if prefix.contains(['b', 'B']) {
+        StringValue::Bytes(Vec::new())
+    } else {
+        StringValue::Str(String::new())
+    };
+
+    // Skip the quotes on both sides
+    let quotes = &literal[prefix_len..checked.offset()];
+    let quote_len =
+        if quotes.len() >= 6 && (quotes.starts_with("'''") || quotes.starts_with("\"\"\"")) {
+            3
+        } else {
+            1
+        };
+    for _ in 0..quote_len {
+        iter.next();
+    }
+    iter.limit(checked.offset() - quote_len - iter.offset());
+
+    while let Some(c) = iter.next() {
+        match c {
+            '\\' if !raw => {
+                let offset = iter.offset() - 1;
+                let (line, column) = iter.pos();
+                let start = (line, column - 1);
+                let bytes = matches!(value, StringValue::Bytes(_));
+                match collect_escape(&mut iter, bytes, &mut warnings)? {
+                    // Octal escapes above `\377` keep their lowest byte, as in CPython
+                    Escape::Value(byte) if bytes => value.push_byte(byte as u8),
+                    Escape::Value(code) => match char::from_u32(code) {
+                        Some(c) => value.push(c),
+                        None => {
+                            let kind = ErrorKind::SurrogateCharacter(code);
+                            return Err(TokenizeError::new(kind, start, iter.pos()));
+                        }
+                    },
+                    Escape::Named(name) => match unicode_names2::character(name) {
+                        Some(c) => value.push(c),
+                        None => {
+                            let kind = ErrorKind::UnknownCharacterName(name.to_owned());
+                            return Err(TokenizeError::new(kind, start, iter.pos()));
+                        }
+                    },
+                    Escape::LineJoin => {}
+                    Escape::Unknown => value.push_str(iter.slice_from(offset)),
+                }
+            }
+            // The lines of the string end with `\n` whatever ends them in the source
+            '\r' if iter.peek() == Some(&'\n') => {}
+            '\r' => value.push('\n'),
+            c => value.push(c),
+        }
+    }
+    Ok(value)
+}
+
+/// Decodes adjacent string literals into the single value Python makes of them,
+/// as in `'Hello, ' "world"`.
+///
+/// No literals make an empty string.
+///
+/// # Errors
+///
+/// * `ErrorKind::MixedBytesAndStr` - If a bytes literal is next to a string literal,
+///   spanning the first literal that does not match the ones before it.
+/// * For the same reasons as [`decode_string`].
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{decode_strings, tokenize, StringValue, Token};
+///
+/// let tokens = tokenize("message = ('Hello, '\n    r\"\\world\")\n").unwrap();
+/// let literals = tokens.iter().filter_map(|token| match token {
+///     Token::String(literal) => Some(literal.as_str()),
+///     _ => None,
+/// });
+///
+/// assert_eq!(
+///     decode_strings(literals).unwrap(),
+///     StringValue::Str("Hello, \\world".to_string())
+/// );
+/// ```
+pub fn decode_strings<'a, I>(literals: I) -> Result<StringValue>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut value = None;
+    for literal in literals {
+        value = Some(match (value, decode_string(literal)?) {
+            (None, next) => next,
+            (Some(StringValue::Str(mut text)), StringValue::Str(next)) => {
+                text.push_str(&next);
+                StringValue::Str(text)
+            }
+            (Some(StringValue::Bytes(mut data)), StringValue::Bytes(next)) => {
+                data.extend(next);
+                StringValue::Bytes(data)
+            }
+            _ => {
+                let kind = ErrorKind::MixedBytesAndStr;
+                return Err(TokenizeError::new(kind, (1, 1), end_of(literal)));
+            }
+        });
+    }
+    Ok(value.unwrap_or_else(|| StringValue::Str(String::new())))
+}
+
+/// The value a number literal stands for at run time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NumberValue {
+    /// The value of an integer literal, such as `1_000` or `0x10`.
+    Int(i128),
+    /// The value of a float literal, such as `1.5` or `1e3`.
+    Float(f64),
+    /// The imaginary part of an imaginary literal, such as the `2.0` of `2j`.
+    Imaginary(f64),
+}
+
+/// Decodes a number literal, such as the text of a `Token::Number`, into its value.
+///
+/// Integers that Python would hold in arbitrary precision are checked to fit in an `i128`.
+/// Floats are rounded to the nearest `f64` like in Python,
+/// so literals too large for it become infinity.
+///
+/// # Errors
+///
+/// * `ErrorKind::IntegerOverflow` - If an integer is larger than `i128::MAX`.
+/// * For the same reasons as the tokenizer, if the literal is malformed.
+///
+/// The positions of the errors are counted from the start of the literal.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{decode_number, NumberValue};
+///
+/// assert_eq!(decode_number("1_000").unwrap(), NumberValue::Int(1000));
+/// assert_eq!(decode_number("0x_ff").unwrap(), NumberValue::Int(255));
+/// assert_eq!(decode_number("1e3").unwrap(), NumberValue::Float(1000.0));
+/// assert_eq!(decode_number("2J").unwrap(), NumberValue::Imaginary(2.0));
+/// ```
+pub fn decode_number(literal: &str) -> Result<NumberValue> {
+    let mut iter = PeekableCharTracker::new(literal);
+    // A float like `.5` is collected after its point
+    iter.next_if(|c| *c == '.');
+    collect_number(&mut iter, 0)?;
+    if iter.peek().is_some() {
+        return Err(TokenizeError::at(
+            ErrorKind::InvalidDecimalLiteral,
+            iter.pos(),
+        ));
+    }
+
+    let digits = literal.replace('_', "");
+    let radix = match digits.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0o" | "0O") => 8,
+        Some("0b" | "0B") => 2,
+        _ => 10,
+    };
+    let overflow = || TokenizeError::new(ErrorKind::IntegerOverflow, (1, 1), iter.pos());
+    if radix != 10 {
+        return match i128::from_str_radix(&digits[2..], radix) {
+            Ok(value) => Ok(NumberValue::Int(value)),
+            Err(_) => Err(overflow()),
+        };
+    }
+    if let Some(imaginary) = digits.strip_suffix(|c| c == 'j' || c == 'J') {
+        return Ok(NumberValue::Imaginary(parse_float(imaginary)));
+    }
+    if digits.contains(['.', 'e', 'E']) {
+        return Ok(NumberValue::Float(parse_float(&digits)));
+    }
+    match digits.parse() {
+        Ok(value) => Ok(NumberValue::Int(value)),
+        Err(_) => Err(overflow()),
+    }
+}
+
+/// Parses the digits of a float that the tokenizer has checked.
+fn parse_float(digits: &str) -> f64 {
+    digits.parse().expect("the float literal was checked by the tokenizer")
+}
+
+/// Returns the position just past the end of the literal.
+fn end_of(literal: &str) -> (usize, usize) {
+    let mut iter = PeekableCharTracker::new(literal);
+    while iter.next().is_some() {}
+    iter.pos()
+}
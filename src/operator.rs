@@ -0,0 +1,232 @@
+/// The kind of an operator token, like the `exact_type` of a token in Python's `tokenize`.
+///
+/// The variants are named after the token types of Python's `token` module,
+/// and together they are the operators of its `EXACT_TOKEN_TYPES`.
+///
+/// # Example
+///
+/// ```rust
+/// use tokenizer_py::OperatorKind;
+///
+/// assert_eq!(OperatorKind::from_operator("**="), Some(OperatorKind::DoubleStarEqual));
+/// assert_eq!(OperatorKind::from_operator("$"), None);
+/// assert_eq!(OperatorKind::Ellipsis.as_str(), "...");
+/// assert_eq!(OperatorKind::RArrow.name(), "RARROW");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OperatorKind {
+    /// `(`
+    LPar,
+    /// `)`
+    RPar,
+    /// `[`
+    LSqb,
+    /// `]`
+    RSqb,
+    /// `:`
+    Colon,
+    /// `,`
+    Comma,
+    /// `;`
+    Semi,
+    /// `+`
+    Plus,
+    /// `-`
+    Minus,
+    /// `*`
+    Star,
+    /// `/`
+    Slash,
+    /// `|`
+    VBar,
+    /// `&`
+    Amper,
+    /// `<`
+    Less,
+    /// `>`
+    Greater,
+    /// `=`
+    Equal,
+    /// `.`
+    Dot,
+    /// `%`
+    Percent,
+    /// `{`
+    LBrace,
+    /// `}`
+    RBrace,
+    /// `==`
+    EqEqual,
+    /// `!=`
+    NotEqual,
+    /// `<=`
+    LessEqual,
+    /// `>=`
+    GreaterEqual,
+    /// `~`
+    Tilde,
+    /// `^`
+    Circumflex,
+    /// `<<`
+    LeftShift,
+    /// `>>`
+    RightShift,
+    /// `**`
+    DoubleStar,
+    /// `+=`
+    PlusEqual,
+    /// `-=`
+    MinEqual,
+    /// `*=`
+    StarEqual,
+    /// `/=`
+    SlashEqual,
+    /// `%=`
+    PercentEqual,
+    /// `&=`
+    AmperEqual,
+    /// `|=`
+    VBarEqual,
+    /// `^=`
+    CircumflexEqual,
+    /// `<<=`
+    LeftShiftEqual,
+    /// `>>=`
+    RightShiftEqual,
+    /// `**=`
+    DoubleStarEqual,
+    /// `//`
+    DoubleSlash,
+    /// `//=`
+    DoubleSlashEqual,
+    /// `@`
+    At,
+    /// `@=`
+    AtEqual,
+    /// `->`
+    RArrow,
+    /// `...`
+    Ellipsis,
+    /// `:=`
+    ColonEqual,
+    /// `!`, which only separates the conversion in a replacement field of an f-string.
+    Exclamation,
+}
+
+/// Every operator of Python with its kind.
+const EXACT_OPERATORS: [(&str, OperatorKind); 48] = [
+    ("(", OperatorKind::LPar),
+    (")", OperatorKind::RPar),
+    ("[", OperatorKind::LSqb),
+    ("]", OperatorKind::RSqb),
+    (":", OperatorKind::Colon),
+    (",", OperatorKind::Comma),
+    (";", OperatorKind::Semi),
+    ("+", OperatorKind::Plus),
+    ("-", OperatorKind::Minus),
+    ("*", OperatorKind::Star),
+    ("/", OperatorKind::Slash),
+    ("|", OperatorKind::VBar),
+    ("&", OperatorKind::Amper),
+    ("<", OperatorKind::Less),
+    (">", OperatorKind::Greater),
+    ("=", OperatorKind::Equal),
+    (".", OperatorKind::Dot),
+    ("%", OperatorKind::Percent),
+    ("{", OperatorKind::LBrace),
+    ("}", OperatorKind::RBrace),
+    ("==", OperatorKind::EqEqual),
+    ("!=", OperatorKind::NotEqual),
+    ("<=", OperatorKind::LessEqual),
+    (">=", OperatorKind::GreaterEqual),
+    ("~", OperatorKind::Tilde),
+    ("^", OperatorKind::Circumflex),
+    ("<<", OperatorKind::LeftShift),
+    (">>", OperatorKind::RightShift),
+    ("**", OperatorKind::DoubleStar),
+    ("+=", OperatorKind::PlusEqual),
+    ("-=", OperatorKind::MinEqual),
+    ("*=", OperatorKind::StarEqual),
+    ("/=", OperatorKind::SlashEqual),
+    ("%=", OperatorKind::PercentEqual),
+    ("&=", OperatorKind::AmperEqual),
+    ("|=", OperatorKind::VBarEqual),
+    ("^=", OperatorKind::CircumflexEqual),
+    ("<<=", OperatorKind::LeftShiftEqual),
+    (">>=", OperatorKind::RightShiftEqual),
+    ("**=", OperatorKind::DoubleStarEqual),
+    ("//", OperatorKind::DoubleSlash),
+    ("//=", OperatorKind::DoubleSlashEqual),
+    ("@", OperatorKind::At),
+    ("@=", OperatorKind::AtEqual),
+    ("->", OperatorKind::RArrow),
+    ("...", OperatorKind::Ellipsis),
+    (":=", OperatorKind::ColonEqual),
+    ("!", OperatorKind::Exclamation),
+];
+
+impl OperatorKind {
+    /// Returns the kind of the operator, or `None` if Python has no such operator.
+    pub fn from_operator(operator: &str) -> Option<Self> {
+        EXACT_OPERATORS.iter().find(|(text, _)| *text == operator).map(|(_, kind)| *kind)
+    }
+
+    /// Returns the text of the operator.
+    pub fn as_str(&self) -> &'static str {
+        EXACT_OPERATORS.iter().find(|(_, kind)| kind == self).map(|(text, _)| *text).unwrap()
+    }
+
+    /// Returns the name of the token type of the operator in Python's `token` module.
+    pub fn name(&self) -> &'static str {
+        match self {
+            OperatorKind::LPar => "LPAR",
+            OperatorKind::RPar => "RPAR",
+            OperatorKind::LSqb => "LSQB",
+            OperatorKind::RSqb => "RSQB",
+            OperatorKind::Colon => "COLON",
+            OperatorKind::Comma => "COMMA",
+            OperatorKind::Semi => "SEMI",
+            OperatorKind::Plus => "PLUS",
+            OperatorKind::Minus => "MINUS",
+            OperatorKind::Star => "STAR",
+            OperatorKind::Slash => "SLASH",
+            OperatorKind::VBar => "VBAR",
+            OperatorKind::Amper => "AMPER",
+            OperatorKind::Less => "LESS",
+            OperatorKind::Greater => "GREATER",
+            OperatorKind::Equal => "EQUAL",
+            OperatorKind::Dot => "DOT",
+            OperatorKind::Percent => "PERCENT",
+            OperatorKind::LBrace => "LBRACE",
+            OperatorKind::RBrace => "RBRACE",
+            OperatorKind::EqEqual => "EQEQUAL",
+            OperatorKind::NotEqual => "NOTEQUAL",
+            OperatorKind::LessEqual => "LESSEQUAL",
+            OperatorKind::GreaterEqual => "GREATEREQUAL",
+            OperatorKind::Tilde => "TILDE",
+            OperatorKind::Circumflex => "CIRCUMFLEX",
+            OperatorKind::LeftShift => "LEFTSHIFT",
+            OperatorKind::RightShift => "RIGHTSHIFT",
+            OperatorKind::DoubleStar => "DOUBLESTAR",
+            OperatorKind::PlusEqual => "PLUSEQUAL",
+            OperatorKind::MinEqual => "MINEQUAL",
+            OperatorKind::StarEqual => "STAREQUAL",
+            OperatorKind::SlashEqual => "SLASHEQUAL",
+            OperatorKind::PercentEqual => "PERCENTEQUAL",
+            OperatorKind::AmperEqual => "AMPEREQUAL",
+            OperatorKind::VBarEqual => "VBAREQUAL",
+            OperatorKind::CircumflexEqual => "CIRCUMFLEXEQUAL",
+            OperatorKind::LeftShiftEqual => "LEFTSHIFTEQUAL",
+            OperatorKind::RightShiftEqual => "RIGHTSHIFTEQUAL",
+            OperatorKind::DoubleStarEqual => "DOUBLESTAREQUAL",
+            OperatorKind::DoubleSlash => "DOUBLESLASH",
+            OperatorKind::DoubleSlashEqual => "DOUBLESLASHEQUAL",
+            OperatorKind::At => "AT",
+            OperatorKind::AtEqual => "ATEQUAL",
+            OperatorKind::RArrow => "RARROW",
+            OperatorKind::Ellipsis => "ELLIPSIS",
+            OperatorKind::ColonEqual => "COLONEQUAL",
+            OperatorKind::Exclamation => "EXCLAMATION",
+        }
+    }
+}
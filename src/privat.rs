@@ -3,15 +3,24 @@ use std::str::Chars;
 
 /// A struct that wraps a Peekable iterator over Chars with position tracking.
 pub struct PeekableCharTracker<'a> {
+    src: &'a str,
     iter: Peekable<Chars<'a>>,
     pos: (usize, usize),
+    offset: usize,
+    line_offset: usize,
 }
 
 impl<'a> PeekableCharTracker<'a> {
     /// Creates a new PeekableCharTracker instance.
     #[inline]
-    pub const fn new(iter: Peekable<Chars<'a>>) -> Self {
-        PeekableCharTracker { iter, pos: (1, 1) }
+    pub fn new(src: &'a str) -> Self {
+        PeekableCharTracker {
+            src,
+            iter: src.chars().peekable(),
+            pos: (1, 1),
+            offset: 0,
+            line_offset: 0,
+        }
     }
 
     /// Returns a current position.
@@ -20,6 +29,42 @@ impl<'a> PeekableCharTracker<'a> {
         self.pos
     }
 
+    /// Moves the current position to the start of the given line.
+    #[inline]
+    pub fn set_line(&mut self, line: usize) {
+        self.pos = (line, 1);
+    }
+
+    /// Returns the byte offset of the current position in the source.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the byte offset of the start of the current line.
+    #[inline]
+    pub fn line_offset(&self) -> usize {
+        self.line_offset
+    }
+
+    /// Returns the whole source the iterator walks over.
+    #[inline]
+    pub fn src(&self) -> &'a str {
+        self.src
+    }
+
+    /// Returns the source text from the byte offset `start` up to the current position.
+    #[inline]
+    pub fn slice_from(&self, start: usize) -> &'a str {
+        &self.src[start..self.offset]
+    }
+
+    /// Restricts the iterator to the next `len` bytes of the source.
+    #[inline]
+    pub fn limit(&mut self, len: usize) {
+        self.iter = self.src[self.offset..self.offset + len].chars().peekable();
+    }
+
     /// Peeks at the next character without advancing the iterator.
     #[inline]
     pub fn peek(&mut self) -> Option<&char> {
@@ -36,11 +81,16 @@ impl<'a> PeekableCharTracker<'a> {
     /// Helper function to handle newline characters and update position.
     #[inline]
     fn check_newline(&mut self, c: Option<char>) -> Option<char> {
+        if let Some(c) = c {
+            self.offset += c.len_utf8();
+        }
         match c {
-            Some('\n') => {
+            // A lone `\r` ends a line as `\n` and `\r\n` do
+            Some(c @ ('\n' | '\r')) if c == '\n' || self.iter.peek() != Some(&'\n') => {
                 self.pos.0 += 1;
                 self.pos.1 = 1;
-                Some('\n')
+                self.line_offset = self.offset;
+                Some(c)
             }
             Some(other) => {
                 self.pos.1 += 1;
@@ -49,12 +99,22 @@ impl<'a> PeekableCharTracker<'a> {
             None => None
         }
     }
+}
 
-    /// Checks if the current position is at the start of a new line.
-    #[inline]
-    pub fn is_start_of_line(&self) -> bool {
-        self.pos.1 == 1
-    }
+/// Splits a text into its lines without their line endings,
+/// where a lone `\r` ends a line as `\n` and `\r\n` do.
+pub fn lines(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(text);
+    std::iter::from_fn(move || {
+        let text = rest?;
+        let Some(end) = text.find(['\n', '\r']) else {
+            rest = None;
+            return Some(text);
+        };
+        let ending = if text[end..].starts_with("\r\n") { 2 } else { 1 };
+        rest = Some(&text[end + ending..]);
+        Some(&text[..end])
+    })
 }
 
 impl Iterator for PeekableCharTracker<'_> {
@@ -73,8 +133,11 @@ impl Clone for PeekableCharTracker<'_> {
     #[inline]
     fn clone(&self) -> Self {
         PeekableCharTracker {
+            src: self.src,
             iter: self.iter.clone(),
             pos: self.pos,
+            offset: self.offset,
+            line_offset: self.line_offset,
         }
     }
 }
\ No newline at end of file
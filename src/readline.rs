@@ -0,0 +1,243 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+
+use crate::privat::lines;
+use crate::tokenizer::LineContext;
+use crate::{ErrorKind, Result, TokenInfo, TokenizeError, Tokenizer, TokenizerConfig};
+
+/// An iterator that tokenizes text read line by line,
+/// created by [`generate_tokens`] or [`tokenize_reader`].
+///
+/// Only the current logical line is kept in memory. Lines are read ahead
+/// while a token (like a triple-quoted string) continues past the end of a line,
+/// and the indentation and open brackets carry over from one line to the next.
+pub struct GenerateTokens<F> {
+    readline: F,
+    buffer: String,
+    line: usize,
+    context: LineContext,
+    config: TokenizerConfig,
+    /// The quotes that end the triple-quoted string going on past the buffer,
+    /// which is not tokenized again before a line has them.
+    closing_quote: Option<String>,
+    pending: VecDeque<TokenInfo>,
+    error: Option<TokenizeError>,
+    finished: bool,
+}
+
+/// Tokenizes text produced by the `readline` callback, like Python's
+/// `tokenize.generate_tokens`.
+///
+/// Each call to `readline` returns the next line including its line ending,
+/// or an empty string when there is no more input.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{generate_tokens, Token};
+///
+/// let mut lines = vec!["s = '''a\n", "b'''\n"].into_iter();
+/// let tokens = generate_tokens(|| Ok(lines.next().unwrap_or("").to_string()))
+///     .map(|info| info.map(|info| info.token))
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+///
+/// assert_eq!(tokens, vec![
+///     Token::Name("s".to_string()),
+///     Token::OP("=".to_string()),
+///     Token::String("'''a\nb'''".to_string()),
+///     Token::NewLine,
+///     Token::EndMarker,
+/// ]);
+/// ```
+pub fn generate_tokens<F>(readline: F) -> GenerateTokens<F>
+where
+    F: FnMut() -> io::Result<String>,
+{
+    generate_tokens_with_config(readline, TokenizerConfig::default())
+}
+
+/// Tokenizes text produced by the `readline` callback with the given settings,
+/// like [`generate_tokens`].
+///
+/// The first error still ends the tokens, so the `recover` setting is left out.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{generate_tokens_with_config, Feature, PythonVersion, TokenizerConfig};
+///
+/// let config = TokenizerConfig {
+///     version: PythonVersion::new(3, 7),
+///     ..TokenizerConfig::default()
+/// };
+/// let mut lines = vec!["if (n := 10) > 5:\n", "    pass\n"].into_iter();
+/// let err = generate_tokens_with_config(|| Ok(lines.next().unwrap_or("").to_string()), config)
+///     .find_map(|info| info.err())
+///     .unwrap();
+///
+/// assert_eq!(err.start, (1, 7));
+/// ```
+pub fn generate_tokens_with_config<F>(readline: F, config: TokenizerConfig) -> GenerateTokens<F>
+where
+    F: FnMut() -> io::Result<String>,
+{
+    GenerateTokens {
+        readline,
+        buffer: String::new(),
+        line: 1,
+        context: LineContext::default(),
+        config: TokenizerConfig {
+            recover: false,
+            ..config
+        },
+        closing_quote: None,
+        pending: VecDeque::new(),
+        error: None,
+        finished: false,
+    }
+}
+
+/// Tokenizes text read line by line from a `BufRead`.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use tokenizer_py::{tokenize_reader, Token};
+///
+/// let reader = Cursor::new("x = (1,\n     2)\n");
+/// let tokens = tokenize_reader(reader)
+///     .map(|info| info.unwrap().token)
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(tokens[5], Token::NL);
+/// assert_eq!(tokens.len(), 10);
+/// ```
+pub fn tokenize_reader<R: BufRead>(
+    reader: R,
+) -> GenerateTokens<impl FnMut() -> io::Result<String>> {
+    tokenize_reader_with_config(reader, TokenizerConfig::default())
+}
+
+/// Tokenizes text read line by line from a `BufRead` with the given settings,
+/// like [`generate_tokens_with_config`].
+pub fn tokenize_reader_with_config<R: BufRead>(
+    mut reader: R,
+    config: TokenizerConfig,
+) -> GenerateTokens<impl FnMut() -> io::Result<String>> {
+    generate_tokens_with_config(
+        move || {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            Ok(line)
+        },
+        config,
+    )
+}
+
+impl<F> GenerateTokens<F>
+where
+    F: FnMut() -> io::Result<String>,
+{
+    /// Reads lines until they form complete tokens and queues those tokens,
+    /// or the error that stops the tokenization.
+    fn read_logical_line(&mut self) {
+        loop {
+            let line = match (self.readline)() {
+                Ok(line) => line,
+                Err(err) => {
+                    self.error = Some(TokenizeError::at(
+                        ErrorKind::Io(err.to_string()),
+                        (self.line, 1),
+                    ));
+                    self.finished = true;
+                    return;
+                }
+            };
+            // Only the last line can lack a line ending
+            let last = !line.ends_with(['\n', '\r']);
+            self.buffer.push_str(&line);
+            if let Some(quote) = &self.closing_quote {
+                if !last && !line.contains(quote.as_str()) {
+                    continue;
+                }
+            }
+
+            let context = self.context.clone();
+            let mut tokenizer =
+                Tokenizer::resume(&self.buffer, self.line, context, last, self.config);
+            let mut tokens = Vec::new();
+            let mut error = None;
+            while let Some(info) = tokenizer.next_info() {
+                match info {
+                    Ok(info) => tokens.push(info),
+                    Err(err) => error = Some(err),
+                }
+            }
+
+            match error {
+                // The token goes on in the lines that have not been read yet
+                Some(err) if !last && tokenizer.at_end() => {
+                    self.closing_quote = self.closing_quote_of(&err);
+                    continue;
+                }
+                Some(err) => {
+                    self.pending.extend(tokens);
+                    self.error = Some(err);
+                    self.finished = true;
+                    return;
+                }
+                None => {}
+            }
+
+            self.context = tokenizer.into_context();
+            self.closing_quote = None;
+            self.line += lines(&self.buffer).count() - 1;
+            self.buffer.clear();
+            self.pending.extend(tokens);
+            self.finished = last;
+            return;
+        }
+    }
+
+    /// Returns the quotes that end the string of an unterminated triple-quoted string error,
+    /// or `None` for any other error.
+    fn closing_quote_of(&self, err: &TokenizeError) -> Option<String> {
+        if !matches!(
+            err.kind,
+            ErrorKind::UnterminatedTripleQuotedString
+                | ErrorKind::UnterminatedTripleQuotedFString
+                | ErrorKind::UnterminatedTripleQuotedTString
+        ) {
+            return None;
+        }
+        let (line, col) = err.start;
+        let start_line = lines(&self.buffer).nth(line - self.line)?;
+        let string = start_line.chars().skip(col - 1).skip_while(char::is_ascii_alphabetic);
+        Some(string.take(3).collect())
+    }
+}
+
+impl<F> Iterator for GenerateTokens<F>
+where
+    F: FnMut() -> io::Result<String>,
+{
+    type Item = Result<TokenInfo>;
+
+    /// Returns the next token together with its location.
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(info) = self.pending.pop_front() {
+                return Some(Ok(info));
+            }
+            if let Some(err) = self.error.take() {
+                return Some(Err(err));
+            }
+            if self.finished {
+                return None;
+            }
+            self.read_logical_line();
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt::{Display, Formatter};
+
+use crate::{privat::lines, TokenizeError};
+
+/// A rendering of a [`TokenizeError`] that shows where in the source it was found.
+///
+/// The offending line is printed with carets under the span of the error,
+/// together with the file name and an optional help note,
+/// in the style of the compiler diagnostics.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{tokenize, Report};
+///
+/// let source = "x = 1\ns = 'abc\n";
+/// let err = tokenize(source).unwrap_err();
+/// let report = Report::new(&err, source)
+///     .file("example.py")
+///     .help("close the string with a matching quote");
+///
+/// assert_eq!(
+///     report.to_string(),
+///     "error[E0101]: unterminated string literal\n \
+///       --> example.py:2:5\n  \
+///        |\n\
+///      2 | s = 'abc\n  \
+///        |     ^^^^\n  \
+///        |\n  \
+///        = help: close the string with a matching quote\n"
+/// );
+/// ```
+pub struct Report<'a> {
+    error: &'a TokenizeError,
+    source: &'a str,
+    file: Option<&'a str>,
+    help: Option<&'a str>,
+}
+
+impl<'a> Report<'a> {
+    /// Creates a report of the error found in the given source.
+    pub fn new(error: &'a TokenizeError, source: &'a str) -> Self {
+        Report {
+            error,
+            source,
+            file: None,
+            help: None,
+        }
+    }
+
+    /// Sets the name of the file the source was read from.
+    pub fn file(mut self, file: &'a str) -> Self {
+        self.file = Some(file);
+        self
+    }
+
+    /// Adds a help note printed below the source line.
+    pub fn help(mut self, help: &'a str) -> Self {
+        self.help = Some(help);
+        self
+    }
+}
+
+impl Display for Report<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (line, column) = self.error.start;
+        let text = lines(self.source).nth(line.saturating_sub(1)).unwrap_or("");
+        let gutter = " ".repeat(line.to_string().len());
+
+        // Tabs are kept under the line, so that the caret lines up with it however they are shown
+        let padding = text
+            .chars()
+            .chain(std::iter::repeat(' '))
+            .take(column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        // An error that goes on to the next lines is underlined up to the end of its first line
+        let width = if self.error.end.0 == line {
+            self.error.end.1.saturating_sub(column)
+        } else {
+            text.chars().count().saturating_sub(column - 1)
+        };
+
+        writeln!(f, "error[{}]: {}", self.error.code(), self.error.message())?;
+        writeln!(
+            f,
+            "{}--> {}:{}:{}",
+            gutter,
+            self.file.unwrap_or("<string>"),
+            line,
+            column
+        )?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", line, text)?;
+        writeln!(f, "{} | {}{}", gutter, padding, "^".repeat(width.max(1)))?;
+        if let Some(help) = self.help {
+            writeln!(f, "{} |", gutter)?;
+            writeln!(f, "{} = help: {}", gutter, help)?;
+        }
+        Ok(())
+    }
+}
@@ -28,13 +28,15 @@ fn test_tokenize_1_level_of_indent() {
 
 #[test]
 fn test_tokenize_different_indent_levels() {
-    let actual_tokens = tokenize("level_1\n  level_2\n    level_3").unwrap();
+    let actual_tokens = tokenize("level_1:\n  level_2:\n    level_3").unwrap();
     use Token::*;
     let expected_tokens = vec![
         Name("level_1".to_owned()),
+        OP(":".to_owned()),
         NewLine,
         Indent("  ".to_owned()),
         Name("level_2".to_owned()),
+        OP(":".to_owned()),
         NewLine,
         Indent("    ".to_owned()),
         Name("level_3".to_owned()),
@@ -92,7 +94,7 @@ fn test_tokenize_numbers() {
 
 #[test]
 fn test_tokenize_fstring() {
-    let actual_tokens = tokenize("f\"midle {2 + 2 = ?}\"").unwrap();
+    let actual_tokens = tokenize("f\"midle {2 + 2 = }\"").unwrap();
     use Token::*;
     let expected_tokens = vec![
         FStringStart("f\"".to_owned()),
@@ -102,7 +104,6 @@ fn test_tokenize_fstring() {
         OP("+".to_owned()),
         Number("2".to_owned()),
         OP("=".to_owned()),
-        OP("?".to_owned()),
         OP("}".to_owned()),
         FStringEnd("\"".to_owned()),
         NewLine,
@@ -124,7 +125,8 @@ fn test_tokenize_operators() {
         OP("%".to_owned()),
         OP("&".to_owned()),
         OP("|".to_owned()),
-        OP("<>".to_owned()),
+        OP("<".to_owned()),
+        OP(">".to_owned()),
         OP("!".to_owned()),
         OP("^".to_owned()),
         OP(":".to_owned()),
@@ -138,10 +140,7 @@ fn test_tokenize_operators() {
         OP("{".to_owned()),
         OP("}".to_owned()),
         OP("@".to_owned()),
-        OP("$".to_owned()),
-        OP("?".to_owned()),
         OP("~".to_owned()),
-        OP("`".to_owned()),
         NewLine,
         EndMarker,
     ];
@@ -210,3 +209,1730 @@ TokenizeError::EscapeSeq(
     ];
     assert_eq!(actual, expected);
 }
+
+#[test]
+fn test_tokenize_info_positions() {
+    let actual = tokenize_info("if x:\n  a = f\"{b}\"\n").unwrap();
+    let actual: Vec<_> =
+        actual.into_iter().map(|info| (info.token, info.start, info.end)).collect();
+    use Token::*;
+    let expected = vec![
+        (Name("if".to_owned()), (1, 0), (1, 2)),
+        (Name("x".to_owned()), (1, 3), (1, 4)),
+        (OP(":".to_owned()), (1, 4), (1, 5)),
+        (NewLine, (1, 5), (1, 6)),
+        (Indent("  ".to_owned()), (2, 0), (2, 2)),
+        (Name("a".to_owned()), (2, 2), (2, 3)),
+        (OP("=".to_owned()), (2, 4), (2, 5)),
+        (FStringStart("f\"".to_owned()), (2, 6), (2, 8)),
+        (OP("{".to_owned()), (2, 8), (2, 9)),
+        (Name("b".to_owned()), (2, 9), (2, 10)),
+        (OP("}".to_owned()), (2, 10), (2, 11)),
+        (FStringEnd("\"".to_owned()), (2, 11), (2, 12)),
+        (NewLine, (2, 12), (2, 13)),
+        (Dedent, (3, 0), (3, 0)),
+        (EndMarker, (3, 0), (3, 0)),
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_tokenize_info_lines() {
+    let actual = tokenize_info("x = '''a\nb'''\ny").unwrap();
+    assert_eq!(actual[2].line, "x = '''a\nb'''\n");
+    assert_eq!(actual[4].line, "y");
+    assert_eq!(actual[6].line, "");
+}
+
+#[test]
+fn test_tokenizer_blank_and_comment_lines() {
+    let actual: Vec<Token> =
+        Tokenizer::new("if x:\n    a\n\n# note\n    b\n").collect::<Result<_>>().unwrap();
+    use Token::*;
+    let expected = vec![
+        Name("if".to_owned()),
+        Name("x".to_owned()),
+        OP(":".to_owned()),
+        NewLine,
+        Indent("    ".to_owned()),
+        Name("a".to_owned()),
+        NewLine,
+        NL,
+        Comment("# note".to_owned()),
+        NL,
+        Name("b".to_owned()),
+        NewLine,
+        Dedent,
+        EndMarker,
+    ];
+    assert_eq!(actual, expected);
+
+    // A last line with only a comment ends as a blank line, even without a newline
+    let tokens = tokenize_info("# hi").unwrap();
+    assert_eq!(tokens[1].token, NL);
+    assert_eq!((tokens[1].start, tokens[1].end), ((1, 4), (1, 5)));
+    assert_eq!(
+        tokenize("x = 1\n  # hi").unwrap()[4..],
+        [Comment("# hi".to_owned()), NL, EndMarker]
+    );
+}
+
+#[test]
+fn test_tokenizer_stops_after_error() {
+    let mut tokenizer = Tokenizer::new("a 'b\nc");
+    assert_eq!(tokenizer.next(), Some(Ok(Token::Name("a".to_owned()))));
+    assert_eq!(
+        tokenizer.next(),
+        Some(Err(TokenizeError::new(
+            ErrorKind::UnterminatedString,
+            (1, 3),
+            (1, 5)
+        )))
+    );
+    assert_eq!(tokenizer.next(), None);
+}
+
+#[test]
+fn test_tokenize_ref_borrows_source() {
+    let text = "def f(x):\n    return f'{x}' + \"\"\"a\nb\"\"\"  # done\n";
+    let borrowed = tokenize_ref(text).unwrap();
+    let range = text.as_bytes().as_ptr_range();
+    for token in &borrowed {
+        use TokenRef::*;
+        if let Name(s) | Number(s) | String(s) | OP(s) | Indent(s) | Comment(s) | FStringStart(s)
+        | FStringMiddle(s) | FStringEnd(s) = token
+        {
+            assert!(range.contains(&s.as_ptr()), "{:?} is not borrowed", token);
+        }
+    }
+    let owned: Vec<Token> = borrowed.into_iter().map(Token::from).collect();
+    assert_eq!(owned, tokenize(text).unwrap());
+}
+
+#[test]
+fn test_generate_tokens_matches_tokenize_info() {
+    let text = "def f(a,\n      b):\n    s = '''x\n\ny'''\n    t = f'''{a}\n{b}'''\n\n    return (s,\n  t)\nf(1, 2)";
+    let mut lines = text.split_inclusive('\n');
+    let actual = generate_tokens(|| Ok(lines.next().unwrap_or("").to_owned()))
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(actual, tokenize_info(text).unwrap());
+}
+
+#[test]
+fn test_generate_tokens_carriage_returns() {
+    // Lines that end in a lone `\r` are not the last line
+    let mut lines = vec!["x = 1\r", "s = '''a\r", "b'''\r", "y = 2\r"].into_iter();
+    let actual = generate_tokens(|| Ok(lines.next().unwrap_or("").to_owned()))
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(
+        actual,
+        tokenize_info("x = 1\rs = '''a\rb'''\ry = 2\r").unwrap()
+    );
+    assert_eq!(
+        actual[actual.len() - 3].token,
+        Token::Number("2".to_owned())
+    );
+}
+
+#[test]
+fn test_tokenize_reader_long_string() {
+    // Each line of the string is read once, not every time another one is
+    let text = format!(
+        "s = '''\n{}'''\nt = 1\n",
+        "a line of the string\n".repeat(20_000)
+    );
+    let tokens =
+        tokenize_reader(std::io::Cursor::new(text.as_str())).collect::<Result<Vec<_>>>().unwrap();
+    assert_eq!(tokens, tokenize_info(&text).unwrap());
+    assert_eq!(tokens[2].end, (20_002, 3));
+}
+
+#[test]
+fn test_generate_tokens_errors() {
+    let text = "x = 1\ns = '''never closed\ny\n";
+    let mut lines = text.split_inclusive('\n');
+    let actual: Vec<_> = generate_tokens(|| Ok(lines.next().unwrap_or("").to_owned())).collect();
+    assert_eq!(actual.len(), 7);
+    assert_eq!(actual[6], Err(tokenize(text).unwrap_err()));
+
+    let mut calls = 0;
+    let mut actual = generate_tokens(|| {
+        calls += 1;
+        match calls {
+            1 => Ok("x\n".to_owned()),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "broken pipe",
+            )),
+        }
+    });
+    assert_eq!(
+        actual.next().unwrap().unwrap().token,
+        Token::Name("x".to_owned())
+    );
+    assert_eq!(actual.next().unwrap().unwrap().token, Token::NewLine);
+    assert_eq!(
+        actual.next(),
+        Some(Err(TokenizeError::new(
+            ErrorKind::Io("broken pipe".to_owned()),
+            (2, 1),
+            (2, 2)
+        )))
+    );
+    assert_eq!(actual.next(), None);
+}
+
+#[test]
+fn test_tokenize_bytes_encodings() {
+    let tokens = tokenize_bytes(b"\xef\xbb\xbfx\n").unwrap();
+    assert_eq!(tokens[0].token, Token::Encoding("utf-8-sig".to_owned()));
+    assert_eq!(tokens[1].token, Token::Name("x".to_owned()));
+    assert_eq!(tokens[1].start, (1, 0));
+
+    let tokens = tokenize_bytes(b"\n# vim: set fileencoding=cp1252 :\n'\x80'\n").unwrap();
+    assert_eq!(tokens[0].token, Token::Encoding("cp1252".to_owned()));
+    assert_eq!(tokens[4].token, Token::String("'\u{20ac}'".to_owned()));
+
+    assert_eq!(
+        tokenize_bytes(b"# coding: ascii\nx = '\xff'\n"),
+        Err(TokenizeError::new(
+            ErrorKind::UndecodableByte {
+                encoding: "ascii".to_owned(),
+                byte: 0xff
+            },
+            (2, 6),
+            (2, 7)
+        ))
+    );
+    assert_eq!(
+        tokenize_bytes(b"x = '\xc3\xa9\xff'"),
+        Err(TokenizeError::new(
+            ErrorKind::UndecodableByte {
+                encoding: "utf-8".to_owned(),
+                byte: 0xff
+            },
+            (1, 7),
+            (1, 8)
+        ))
+    );
+    assert_eq!(
+        tokenize_bytes(b"x\ry = '\xc3\xa9\xff'"),
+        Err(TokenizeError::new(
+            ErrorKind::UndecodableByte {
+                encoding: "utf-8".to_owned(),
+                byte: 0xff
+            },
+            (2, 7),
+            (2, 8)
+        ))
+    );
+    assert_eq!(
+        tokenize_bytes(b"# coding: foo\n"),
+        Err(TokenizeError::new(
+            ErrorKind::UnknownEncoding("foo".to_owned()),
+            (1, 1),
+            (1, 2)
+        ))
+    );
+    assert_eq!(
+        tokenize_bytes(b"\xef\xbb\xbf# coding: latin-1\n"),
+        Err(TokenizeError::new(
+            ErrorKind::EncodingProblem("utf-8".to_owned()),
+            (1, 1),
+            (1, 2)
+        ))
+    );
+    // The declaration is only looked for on the second line after a blank first line
+    assert_eq!(
+        detect_encoding(b"x = 1\n# coding: latin-1\n").unwrap(),
+        "utf-8"
+    );
+}
+
+#[test]
+fn test_untokenize_round_trip() {
+    let sources = [
+        "import os\n\n\nclass A(object):\n\tdef f(self, x):  # comment\n\t\treturn [x,\n\t\t        1]\n",
+        "s = '''multi\nline'''  ;  t = f'{s}!'\r\nif s:\n    pass\n# trailing comment",
+        "x = 1\n    \n  # indented comment\ny = (\n\n)\n",
+        "if a and \\\n        b:\n    x = 1 + \\\r\n  2\n",
+        "x = (1,\r  2)  # old Mac\ry = '''a\rb''' + \\\r  3\r",
+        // Lines with only a continuation and the whitespace of an open last line
+        "\\\r\n!",
+        "if x:\n\\\r\n\te=",
+        "a\n\\\n\\\r\nb\n",
+        "if x:\n  y\n\\\nz\n",
+        "x\n  ",
+        "x\n\t \x0c",
+    ];
+    for source in sources.iter() {
+        let tokens = tokenize_info(*source).unwrap();
+        assert_eq!(untokenize(&tokens), *source);
+    }
+}
+
+#[test]
+fn test_untokenize_compat_round_trip() {
+    let source = "def f(a, b):\n    if a:\n        return 'x' 'y'\n    return f'{a}' + b  # c\n";
+    let tokens = tokenize(source).unwrap();
+    let rebuilt = untokenize_compat(&tokens);
+    assert_eq!(tokenize(rebuilt).unwrap(), tokens);
+}
+
+#[test]
+fn test_lossless_round_trip() {
+    let sources = [
+        "def f(a,\tb):\r\n    if a:  # check\r\n\r\n        return a\r\n    return b\r\n",
+        "x = [1,\n  2]\n\x0cy = 1 + \\\n    2\n",
+        "s = f'{ a }-{b}'  \n   \n# end",
+        "if x:\n\tpass\n",
+        "x = (1,\r  2)  # old Mac\ry = '''a\rb''' + \\\r  3\r",
+        "",
+    ];
+    for source in sources.iter() {
+        let tokens = tokenize_lossless(source).unwrap();
+        let text = tokens.iter().map(TokenRef::as_str).collect::<String>();
+        assert_eq!(&text, source);
+    }
+}
+
+#[test]
+fn test_lossless_trivia_tokens() {
+    let mut tokenizer = Tokenizer::new("x = 1 + \\\n  2\r\n").lossless(true);
+    let tokens = std::iter::from_fn(|| tokenizer.next_ref()).collect::<Result<Vec<_>>>();
+    assert_eq!(
+        tokens.unwrap(),
+        vec![
+            TokenRef::Name("x"),
+            TokenRef::Whitespace(" "),
+            TokenRef::OP("="),
+            TokenRef::Whitespace(" "),
+            TokenRef::Number("1"),
+            TokenRef::Whitespace(" "),
+            TokenRef::OP("+"),
+            TokenRef::Whitespace(" "),
+            TokenRef::Continuation("\\\n"),
+            TokenRef::Whitespace("  "),
+            TokenRef::Number("2"),
+            TokenRef::NewLine("\r\n"),
+            TokenRef::EndMarker,
+        ]
+    );
+
+    // Without lossless mode the same text has no trivia tokens
+    let tokens = tokenize_info("x = 1\r\n").unwrap();
+    assert_eq!(tokens[3].token, Token::NewLine);
+    assert_eq!((tokens[3].start, tokens[3].end), ((1, 5), (1, 7)));
+}
+
+#[test]
+fn test_line_endings_and_stray_whitespace() {
+    // A lone `\r` ends a line like `\n` and `\r\n` do
+    let tokens = tokenize_info("x = 1\ry = 2\n").unwrap();
+    assert_eq!(tokens[3].token, Token::NewLine);
+    assert_eq!(tokens[3].line, "x = 1\r");
+    assert_eq!(tokens[4].token, Token::Name("y".to_owned()));
+    assert_eq!(tokens[4].start, (2, 0));
+    assert_eq!(
+        privat::lines("a\rb\r\nc\r").collect::<Vec<_>>(),
+        vec!["a", "b", "c", ""]
+    );
+    let mut lines = "x = 1\ry = '''\r'''\n".split_inclusive('\n');
+    let read = generate_tokens(|| Ok(lines.next().unwrap_or("").to_owned()))
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(read, tokenize_info("x = 1\ry = '''\r'''\n").unwrap());
+    assert_eq!(
+        decode_string("'''a\rb\r\nc'''").unwrap(),
+        StringValue::Str("a\nb\nc".to_owned())
+    );
+
+    // Other whitespace can not separate tokens
+    for c in ['\x0b', '\u{a0}', '\u{2028}', '\u{3000}'].iter() {
+        let err = tokenize(format!("a{}= 1", c)).unwrap_err();
+        assert_eq!(
+            err,
+            TokenizeError::new(ErrorKind::InvalidCharacter(*c), (1, 2), (1, 3))
+        );
+    }
+    let mut tokenizer = Tokenizer::new("a\u{a0}= 1\n").lossless(true).recover(true);
+    let mut text = String::new();
+    while let Some(token) = tokenizer.next_ref() {
+        text.push_str(token.unwrap().as_str());
+    }
+    assert_eq!(text, "a\u{a0}= 1\n");
+}
+
+#[test]
+fn test_recover_from_errors() {
+    let source = "a = 'x\nb = 1_ + f'{c}\n\u{a7}d = (1_, 2)\ne = 3\n";
+    let mut tokenizer = Tokenizer::new(source).recover(true);
+    let tokens = tokenizer.by_ref().collect::<Result<Vec<_>>>().unwrap();
+    use Token::*;
+    assert_eq!(
+        tokens,
+        vec![
+            Name("a".to_owned()),
+            OP("=".to_owned()),
+            ErrorToken("'x".to_owned()),
+            NewLine,
+            Name("b".to_owned()),
+            OP("=".to_owned()),
+            ErrorToken("1_".to_owned()),
+            OP("+".to_owned()),
+            ErrorToken("f'{c}".to_owned()),
+            NewLine,
+            ErrorToken("\u{a7}".to_owned()),
+            Name("d".to_owned()),
+            OP("=".to_owned()),
+            OP("(".to_owned()),
+            ErrorToken("1_".to_owned()),
+            OP(",".to_owned()),
+            Number("2".to_owned()),
+            OP(")".to_owned()),
+            NewLine,
+            Name("e".to_owned()),
+            OP("=".to_owned()),
+            Number("3".to_owned()),
+            NewLine,
+            EndMarker,
+        ]
+    );
+    assert_eq!(tokenizer.errors().len(), 5);
+    assert_eq!(tokenizer.errors()[0], tokenize("a = 'x\n").unwrap_err());
+    assert_eq!(
+        tokenizer.errors()[3],
+        TokenizeError::new(ErrorKind::InvalidCharacter('\u{a7}'), (3, 1), (3, 2))
+    );
+}
+
+#[test]
+fn test_recover_at_end_of_text() {
+    let source = "x = '''abc\n\ndef";
+    let mut tokenizer = Tokenizer::new(source).recover(true);
+    let mut tokens = Vec::new();
+    while let Some(info) = tokenizer.next_info() {
+        tokens.push(info.unwrap());
+    }
+    assert_eq!(
+        tokens[2].token,
+        Token::ErrorToken("'''abc\n\ndef".to_owned())
+    );
+    assert_eq!((tokens[2].start, tokens[2].end), ((1, 4), (3, 3)));
+    assert_eq!(tokens.last().unwrap().token, Token::EndMarker);
+    assert_eq!(tokenizer.errors().len(), 1);
+
+    // Without recovering, the same text gives only an error
+    assert!(tokenize(source).is_err());
+
+    // A bracket left open still ends its logical line
+    let source = "x = (1,\n2\n";
+    let mut tokenizer = Tokenizer::new(source).recover(true);
+    let tokens = std::iter::from_fn(|| tokenizer.next_info()).collect::<Result<Vec<_>>>();
+    let tokens = tokens.unwrap();
+    assert_eq!(tokens[7].token, Token::NL);
+    assert_eq!(tokens[8].token, Token::NewLine);
+    assert_eq!((tokens[8].start, tokens[8].end), ((3, 0), (3, 0)));
+    assert_eq!(tokens[9].token, Token::EndMarker);
+    assert_eq!(tokenizer.errors().len(), 1);
+    assert_eq!(untokenize(&tokens), source);
+}
+
+#[test]
+fn test_tokenize_with_errors() {
+    let source = "a = 'x\nb = f'{1_}' + \"y\ndef f():\n    return '''z\n";
+    let (tokens, errors) = tokenize_with_errors(source);
+    use Token::*;
+    assert_eq!(
+        tokens,
+        vec![
+            Name("a".to_owned()),
+            OP("=".to_owned()),
+            ErrorToken("'x".to_owned()),
+            NewLine,
+            Name("b".to_owned()),
+            OP("=".to_owned()),
+            FStringStart("f'".to_owned()),
+            OP("{".to_owned()),
+            ErrorToken("1_".to_owned()),
+            OP("}".to_owned()),
+            FStringEnd("'".to_owned()),
+            OP("+".to_owned()),
+            ErrorToken("\"y".to_owned()),
+            NewLine,
+            Name("def".to_owned()),
+            Name("f".to_owned()),
+            OP("(".to_owned()),
+            OP(")".to_owned()),
+            OP(":".to_owned()),
+            NewLine,
+            Indent("    ".to_owned()),
+            Name("return".to_owned()),
+            ErrorToken("'''z\n".to_owned()),
+            NewLine,
+            Dedent,
+            EndMarker,
+        ]
+    );
+    assert_eq!(errors.len(), 4);
+    assert_eq!(errors[0], tokenize("a = 'x\n").unwrap_err());
+    assert_eq!(errors[1], tokenize("\nb = f'{1_}'\n").unwrap_err());
+
+    let (tokens, errors) = tokenize_with_errors("x = 1\n");
+    assert_eq!(tokens, tokenize("x = 1\n").unwrap());
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn test_report_renders_source_line() {
+    let source = "if x:\n\ty = 1_\n";
+    let err = tokenize(source).unwrap_err();
+    assert_eq!(
+        Report::new(&err, source).to_string(),
+        "error[E0201]: invalid decimal literal\n --> <string>:2:8\n  |\n2 | \ty = 1_\n  | \t      ^\n"
+    );
+
+    // An error that spans several lines is underlined up to the end of its first line
+    let source = "x = '''abc\ndef";
+    let err = tokenize(source).unwrap_err();
+    assert_eq!((err.start, err.end), ((1, 5), (2, 4)));
+    assert_eq!(
+        Report::new(&err, source).to_string(),
+        "error[E0102]: unterminated triple-quoted string literal\n --> <string>:1:5\n  |\n1 | x = '''abc\n  |     ^^^^^^\n"
+    );
+
+    // An error past the last line shows an empty line
+    let err = TokenizeError::new(ErrorKind::UnterminatedTripleQuotedString, (12, 1), (12, 1));
+    assert_eq!(
+        Report::new(&err, source).file("a.py").to_string(),
+        "error[E0102]: unterminated triple-quoted string literal\n  --> a.py:12:1\n   |\n12 | \n   | ^\n"
+    );
+}
+
+#[test]
+fn test_error_kinds_codes_and_messages() {
+    let err = tokenize("s = b'abc\n").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::UnterminatedString);
+    assert_eq!((err.start, err.end), ((1, 5), (1, 10)));
+    assert_eq!(err.message(), "unterminated string literal");
+
+    let err = tokenize("f'''{x}\n").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::UnterminatedTripleQuotedFString);
+    assert_eq!(err.code(), "E0104");
+
+    let kinds = vec![
+        ErrorKind::UnterminatedString,
+        ErrorKind::UnterminatedTripleQuotedString,
+        ErrorKind::UnterminatedFString,
+        ErrorKind::UnterminatedTripleQuotedFString,
+        ErrorKind::InvalidStringPrefix("ub".to_owned()),
+        ErrorKind::InvalidDecimalLiteral,
+        ErrorKind::InvalidCharacter('$'),
+        ErrorKind::Io(String::new()),
+        ErrorKind::UnknownEncoding(String::new()),
+        ErrorKind::EncodingProblem(String::new()),
+        ErrorKind::UndecodableByte {
+            encoding: String::new(),
+            byte: 0,
+        },
+    ];
+    let mut codes = kinds.iter().map(ErrorKind::code).collect::<Vec<_>>();
+    codes.sort_unstable();
+    codes.dedup();
+    assert_eq!(codes.len(), kinds.len());
+    assert_eq!(
+        ErrorKind::InvalidCharacter('$').to_string(),
+        "invalid character '$' (U+0024)"
+    );
+}
+
+#[test]
+fn test_target_version_features() {
+    let tokenize_for = |source: &str, minor: u8| {
+        let config = TokenizerConfig {
+            version: PythonVersion::new(3, minor),
+            ..TokenizerConfig::default()
+        };
+        Tokenizer::with_config(source, config).collect::<Result<Vec<_>>>()
+    };
+    let unsupported = |feature, minor| ErrorKind::UnsupportedFeature {
+        feature,
+        version: PythonVersion::new(3, minor),
+    };
+
+    assert!(tokenize_for("x = 1_000\n", 6).is_ok());
+    let err = tokenize_for("x = 1_000\n", 5).unwrap_err();
+    assert_eq!(err.kind, unsupported(Feature::NumericUnderscores, 5));
+    assert_eq!((err.start, err.end), ((1, 5), (1, 10)));
+    assert_eq!(
+        err.message(),
+        "underscores in numeric literals are not available before Python 3.6, \
+         the target is Python 3.5"
+    );
+    let err = tokenize_for("x = .1_0\n", 5).unwrap_err();
+    assert_eq!(err.kind, unsupported(Feature::NumericUnderscores, 5));
+
+    let err = tokenize_for("s = f'{x}'\n", 5).unwrap_err();
+    assert_eq!(err.kind, unsupported(Feature::FStrings, 5));
+    assert_eq!((err.start, err.end), ((1, 5), (1, 6)));
+    // Before PEP 701 an f-string is a single string token
+    let tokens = tokenize_for("s = f'{x}' + F\"y\"\n", 11).unwrap();
+    assert_eq!(tokens[2], Token::String("f'{x}'".to_owned()));
+    assert_eq!(tokens[4], Token::String("F\"y\"".to_owned()));
+    assert_eq!(
+        tokenize_for("f'{x}'\n", 12).unwrap(),
+        tokenize("f'{x}'\n").unwrap()
+    );
+
+    assert!(tokenize_for("(n := 1)\n", 8).is_ok());
+    let err = tokenize_for("(n := 1)\n", 7).unwrap_err();
+    assert_eq!(err.kind, unsupported(Feature::AssignmentExpressions, 7));
+    assert_eq!((err.start, err.end), ((1, 4), (1, 6)));
+
+    // Version errors are recovered from like any other error
+    let config = TokenizerConfig {
+        version: PythonVersion::new(3, 5),
+        recover: true,
+        ..TokenizerConfig::default()
+    };
+    let mut tokenizer = Tokenizer::with_config("x = 1_0 + f'{y}'\n", config);
+    let tokens = tokenizer.by_ref().collect::<Result<Vec<_>>>().unwrap();
+    assert_eq!(tokens[2], Token::ErrorToken("1_0".to_owned()));
+    assert_eq!(tokens[4], Token::ErrorToken("f'{y}'".to_owned()));
+    assert_eq!(tokenizer.errors().len(), 2);
+}
+
+#[test]
+fn test_target_version_of_other_sources() {
+    let config = TokenizerConfig {
+        version: PythonVersion::new(3, 7),
+        ..TokenizerConfig::default()
+    };
+    let source = "x = 1\nif (n := 10) > 5:\n    pass\n";
+    let expected = tokenize_info_with_config(source, config).unwrap_err();
+    assert_eq!(expected.start, (2, 7));
+
+    let reader = std::io::Cursor::new(source);
+    let err = tokenize_reader_with_config(reader, config).find_map(|info| info.err());
+    assert_eq!(err.as_ref(), Some(&expected));
+    let err = tokenize_bytes_with_config(source.as_bytes(), config).unwrap_err();
+    assert_eq!(err, expected);
+    assert_eq!(
+        tokenize_ref_with_config(source, config).unwrap_err(),
+        expected
+    );
+
+    // The first error is returned even with the recover setting
+    let recover = TokenizerConfig {
+        recover: true,
+        ..config
+    };
+    assert_eq!(
+        tokenize_info_with_config(source, recover).unwrap_err(),
+        expected
+    );
+    let mut lines = source.split_inclusive('\n');
+    let tokens = generate_tokens_with_config(|| Ok(lines.next().unwrap_or("").to_owned()), recover)
+        .collect::<Vec<_>>();
+    assert_eq!(tokens.last(), Some(&Err(expected)));
+}
+
+#[test]
+fn test_tokenize_number_literals() {
+    let numbers = [
+        "0xFF",
+        "0XdeadBEEF",
+        "0x_ff_ff",
+        "0o777",
+        "0O_17",
+        "0b1010",
+        "0B_1_0",
+        "1E5",
+        "1e5",
+        "1e-5",
+        "2.5E+3",
+        "10J",
+        "1_000.000_1",
+        "0",
+        "00",
+        "0_0",
+        "0777.5",
+        "0777e1",
+        "09j",
+        "1.",
+        "1.e5",
+        ".5e3",
+        "1_0.5j",
+    ];
+    for number in numbers.iter() {
+        assert_eq!(
+            tokenize(number).unwrap()[0],
+            Token::Number(number.to_string()),
+            "{}",
+            number
+        );
+    }
+
+    // Some keywords may follow a number directly
+    let tokens = tokenize("1if x else 0x1for").unwrap();
+    assert_eq!(tokens[0], Token::Number("1".to_owned()));
+    assert_eq!(tokens[1], Token::Name("if".to_owned()));
+    assert_eq!(tokens[5], Token::Name("or".to_owned()));
+
+    let errors = [
+        ("0777", ErrorKind::LeadingZeros, (1, 1)),
+        ("0_7", ErrorKind::LeadingZeros, (1, 1)),
+        (
+            "0b12",
+            ErrorKind::InvalidDigit {
+                digit: '2',
+                radix: 2,
+            },
+            (1, 4),
+        ),
+        (
+            "0o8",
+            ErrorKind::InvalidDigit {
+                digit: '8',
+                radix: 8,
+            },
+            (1, 3),
+        ),
+        (
+            "0b1_2",
+            ErrorKind::InvalidDigit {
+                digit: '2',
+                radix: 2,
+            },
+            (1, 5),
+        ),
+        ("0x", ErrorKind::InvalidHexadecimalLiteral, (1, 3)),
+        ("0xfg", ErrorKind::InvalidHexadecimalLiteral, (1, 4)),
+        ("0x_", ErrorKind::InvalidHexadecimalLiteral, (1, 4)),
+        ("0o", ErrorKind::InvalidOctalLiteral, (1, 3)),
+        ("0b", ErrorKind::InvalidBinaryLiteral, (1, 3)),
+        ("1__0", ErrorKind::InvalidDecimalLiteral, (1, 3)),
+        ("1_", ErrorKind::InvalidDecimalLiteral, (1, 3)),
+        ("1e+", ErrorKind::InvalidDecimalLiteral, (1, 4)),
+        ("1e", ErrorKind::InvalidDecimalLiteral, (1, 2)),
+        ("1abc", ErrorKind::InvalidDecimalLiteral, (1, 2)),
+        ("1.real", ErrorKind::InvalidDecimalLiteral, (1, 3)),
+        ("1jx", ErrorKind::InvalidImaginaryLiteral, (1, 3)),
+    ];
+    for (source, kind, start) in errors.iter() {
+        let err = tokenize(source).unwrap_err();
+        assert_eq!((&err.kind, err.start), (kind, *start), "{}", source);
+    }
+    assert_eq!(
+        tokenize("0b12").unwrap_err().message(),
+        "invalid digit '2' in binary literal"
+    );
+}
+
+#[test]
+fn test_tokenize_combined_string_prefixes() {
+    let strings = [
+        "rb'\\d'",
+        "Rb\"x\"",
+        "bR'x'",
+        "BR'...'",
+        "br'''a\nb'''",
+        "u'x'",
+        "U\"x\"",
+        "R'x'",
+    ];
+    for string in strings.iter() {
+        assert_eq!(
+            tokenize(string).unwrap()[0],
+            Token::String(string.to_string()),
+            "{}",
+            string
+        );
+    }
+
+    let prefixes = ["f'", "F\"", "fr'", "Rf'", "rF\"", "FR'"];
+    for prefix in prefixes.iter() {
+        let source = format!("{}{{x}}\\n{}", prefix, &prefix[prefix.len() - 1..]);
+        let tokens = tokenize(&source).unwrap();
+        assert_eq!(
+            tokens[0],
+            Token::FStringStart(prefix.to_string()),
+            "{}",
+            source
+        );
+        assert_eq!(
+            tokens[4],
+            Token::FStringMiddle("\\n".to_owned()),
+            "{}",
+            source
+        );
+    }
+
+    for prefix in ["ub", "bu", "ur", "bf", "fb", "uf", "rr", "bfr"].iter() {
+        let err = tokenize(format!("x = {}'a'", prefix)).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidStringPrefix(prefix.to_string()));
+        assert_eq!((err.start, err.end), ((1, 5), (1, 5 + prefix.len())));
+    }
+
+    // Names that only look like prefixes stay names
+    let tokens = tokenize("rb + fr, bar'x'").unwrap();
+    assert_eq!(tokens[0], Token::Name("rb".to_owned()));
+    assert_eq!(tokens[2], Token::Name("fr".to_owned()));
+
+    // A `\N{...}` escape holds no replacement field, unless the f-string is raw
+    let tokens = tokenize("f'\\N{DASH}{x}' rf'\\N{x}'").unwrap();
+    assert_eq!(tokens[1], Token::FStringMiddle("\\N{DASH}".to_owned()));
+    assert_eq!(tokens[7], Token::FStringMiddle("\\N".to_owned()));
+    assert_eq!(tokens[9], Token::Name("x".to_owned()));
+
+    // An escaped quote does not end an f-string
+    let tokens = tokenize("f'it\\'s {x}'").unwrap();
+    assert_eq!(tokens[1], Token::FStringMiddle("it\\'s ".to_owned()));
+}
+
+/// Tokenizes the text to the end, returning the warnings of the tokenizer.
+fn warnings_of(text: &str) -> Vec<TokenizeWarning> {
+    let mut tokenizer = Tokenizer::new(text);
+    for token in tokenizer.by_ref() {
+        token.unwrap();
+    }
+    tokenizer.warnings().to_vec()
+}
+
+#[test]
+fn test_string_escape_sequences() {
+    let strings = [
+        "'it\\'s'",
+        "\"say \\\"hi\\\"\"",
+        "'''a ' b '' c'''",
+        "'\\x41\\u00e9\\U0001F600\\N{BULLET}\\101\\0'",
+        "b'\\x00\\377'",
+        "'a\\\nb'",
+        "r'\\d\\''",
+    ];
+    for string in strings.iter() {
+        assert_eq!(
+            tokenize(string).unwrap()[0],
+            Token::String(string.to_string()),
+            "{}",
+            string
+        );
+        assert_eq!(warnings_of(string), [], "{}", string);
+    }
+
+    let errors = [
+        ("'\\x4'", ErrorKind::TruncatedEscape('x'), (1, 2), (1, 5)),
+        ("'\\u12'", ErrorKind::TruncatedEscape('u'), (1, 2), (1, 6)),
+        (
+            "'\\U00110000'",
+            ErrorKind::IllegalUnicodeCharacter,
+            (1, 2),
+            (1, 12),
+        ),
+        ("'\\N{}'", ErrorKind::MalformedNamedEscape, (1, 2), (1, 5)),
+        ("'\\Nx'", ErrorKind::MalformedNamedEscape, (1, 2), (1, 4)),
+        ("b'caf\u{e9}'", ErrorKind::NonAsciiBytes, (1, 6), (1, 7)),
+        ("b'\\\u{e9}'", ErrorKind::NonAsciiBytes, (1, 4), (1, 5)),
+        ("rb'\\\u{e9}'", ErrorKind::NonAsciiBytes, (1, 5), (1, 6)),
+    ];
+    for (string, kind, start, end) in errors.iter() {
+        let err = tokenize(string).unwrap_err();
+        assert_eq!(
+            (&err.kind, err.start, err.end),
+            (kind, *start, *end),
+            "{}",
+            string
+        );
+    }
+}
+
+#[test]
+fn test_invalid_escape_sequence_warnings() {
+    use WarningKind::*;
+    assert_eq!(
+        warnings_of("x = '\\d'\ny = '\\777'\n"),
+        [
+            TokenizeWarning::new(InvalidEscapeSequence("\\d".to_owned()), (1, 6), (1, 8)),
+            TokenizeWarning::new(
+                InvalidOctalEscapeSequence("\\777".to_owned()),
+                (2, 6),
+                (2, 10)
+            ),
+        ]
+    );
+    // Bytes have no unicode escapes
+    assert_eq!(
+        warnings_of("b'\\u1234'"),
+        [TokenizeWarning::new(
+            InvalidEscapeSequence("\\u".to_owned()),
+            (1, 3),
+            (1, 5)
+        )]
+    );
+    assert_eq!(
+        warnings_of("f'\\d{x}\\{y}' rf'\\d\\{z}'"),
+        [
+            TokenizeWarning::new(InvalidEscapeSequence("\\d".to_owned()), (1, 3), (1, 5)),
+            TokenizeWarning::new(InvalidEscapeSequence("\\{".to_owned()), (1, 8), (1, 10)),
+        ]
+    );
+    assert_eq!(warnings_of("f'{\"\\d\"}'")[0].start, (1, 5));
+
+    let warning = &warnings_of("'\\d'")[0];
+    assert_eq!(warning.code(), "W0101");
+    assert_eq!(warning.message(), "invalid escape sequence '\\d'");
+}
+
+#[test]
+fn test_decode_string_values() {
+    use StringValue::*;
+    let strings = [
+        ("'abc'", Str("abc".to_owned())),
+        ("\"it\\'s\"", Str("it's".to_owned())),
+        (
+            "'\\a\\b\\f\\n\\r\\t\\v\\\\'",
+            Str("\x07\x08\x0c\n\r\t\x0b\\".to_owned()),
+        ),
+        ("'\\101\\0\\777'", Str("A\0\u{1ff}".to_owned())),
+        (
+            "'\\x41\\u00e9\\U0001F600'",
+            Str("A\u{e9}\u{1f600}".to_owned()),
+        ),
+        (
+            "'\\N{bullet}\\N{LATIN SMALL LETTER A}'",
+            Str("\u{2022}a".to_owned()),
+        ),
+        // Characters are also named by their aliases
+        (
+            "'\\N{NBSP}\\N{LINE FEED}\\N{BYTE ORDER MARK}'",
+            Str("\u{a0}\n\u{feff}".to_owned()),
+        ),
+        ("'\\d\\q'", Str("\\d\\q".to_owned())),
+        ("'''a\r\nb\\\r\nc'''", Str("a\nbc".to_owned())),
+        ("'a\\\nb'", Str("ab".to_owned())),
+        ("''", Str(String::new())),
+        ("''''''", Str(String::new())),
+        ("\"\"\"'a' \"b\" \"\"\"", Str("'a' \"b\" ".to_owned())),
+        ("U'x'", Str("x".to_owned())),
+        ("R'\\n\\''", Str("\\n\\'".to_owned())),
+        ("b'\\x00\\xffabc'", Bytes(b"\x00\xffabc".to_vec())),
+        ("b'\\777\\u1234'", Bytes(b"\xff\\u1234".to_vec())),
+        ("rb'\\x00'", Bytes(b"\\x00".to_vec())),
+    ];
+    for (literal, value) in strings.iter() {
+        assert_eq!(decode_string(literal).as_ref(), Ok(value), "{}", literal);
+    }
+
+    let errors = [
+        ("f'{x}'", ErrorKind::FStringValue, (1, 1), (1, 7)),
+        (
+            "'\\N{NO SUCH NAME}'",
+            ErrorKind::UnknownCharacterName("NO SUCH NAME".to_owned()),
+            (1, 2),
+            (1, 18),
+        ),
+        (
+            "'\\ud800'",
+            ErrorKind::SurrogateCharacter(0xd800),
+            (1, 2),
+            (1, 8),
+        ),
+        (
+            "'''a\n\\x4'''",
+            ErrorKind::TruncatedEscape('x'),
+            (2, 1),
+            (2, 4),
+        ),
+        ("'abc", ErrorKind::UnterminatedString, (1, 1), (1, 5)),
+        // Only one literal is decoded
+        ("'a' 'b'", ErrorKind::InvalidCharacter(' '), (1, 4), (1, 5)),
+        ("'a'b", ErrorKind::InvalidCharacter('b'), (1, 4), (1, 5)),
+        (
+            "abc'x'",
+            ErrorKind::InvalidStringPrefix("abc".to_owned()),
+            (1, 1),
+            (1, 4),
+        ),
+    ];
+    for (literal, kind, start, end) in errors.iter() {
+        let err = decode_string(literal).unwrap_err();
+        assert_eq!(
+            (&err.kind, err.start, err.end),
+            (kind, *start, *end),
+            "{}",
+            literal
+        );
+    }
+
+    let tokens = tokenize("x = 'a' 1").unwrap();
+    assert_eq!(tokens[2].string_value(), Some(Ok(Str("a".to_owned()))));
+    assert_eq!(tokens[3].string_value(), None);
+}
+
+#[test]
+fn test_decode_adjacent_strings() {
+    use StringValue::*;
+    assert_eq!(
+        decode_strings(vec!["'a'", "\"b\"", "r'\\c'"]),
+        Ok(Str("ab\\c".to_owned()))
+    );
+    assert_eq!(
+        decode_strings(vec!["b'a'", "B'\\x62'"]),
+        Ok(Bytes(b"ab".to_vec()))
+    );
+    assert_eq!(decode_strings(Vec::new()), Ok(Str(String::new())));
+
+    let err = decode_strings(vec!["b'a'", "'b'", "b'c'"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::MixedBytesAndStr);
+    assert_eq!((err.start, err.end), ((1, 1), (1, 4)));
+}
+
+#[test]
+fn test_decode_number_values() {
+    use NumberValue::*;
+    let numbers = [
+        ("0", Int(0)),
+        ("000", Int(0)),
+        ("1_000_000", Int(1_000_000)),
+        ("0x_FF", Int(255)),
+        ("0o17", Int(15)),
+        ("0B1010", Int(10)),
+        ("170141183460469231731687303715884105727", Int(i128::MAX)),
+        ("1.5", Float(1.5)),
+        ("1.", Float(1.0)),
+        (".5", Float(0.5)),
+        ("1_0.2_5e-1_0", Float(10.25e-10)),
+        ("1E3", Float(1000.0)),
+        ("1e400", Float(f64::INFINITY)),
+        ("10j", Imaginary(10.0)),
+        ("1.5e2J", Imaginary(150.0)),
+        (".5j", Imaginary(0.5)),
+    ];
+    for (literal, value) in numbers.iter() {
+        assert_eq!(decode_number(literal), Ok(*value), "{}", literal);
+    }
+
+    let errors = [
+        (
+            "170141183460469231731687303715884105728",
+            ErrorKind::IntegerOverflow,
+            (1, 1),
+            (1, 40),
+        ),
+        (
+            "0x1_0000_0000_0000_0000_0000_0000_0000_0000",
+            ErrorKind::IntegerOverflow,
+            (1, 1),
+            (1, 44),
+        ),
+        ("0777", ErrorKind::LeadingZeros, (1, 1), (1, 5)),
+        ("1_", ErrorKind::InvalidDecimalLiteral, (1, 3), (1, 4)),
+        ("1 2", ErrorKind::InvalidDecimalLiteral, (1, 2), (1, 3)),
+    ];
+    for (literal, kind, start, end) in errors.iter() {
+        let err = decode_number(literal).unwrap_err();
+        assert_eq!(
+            (&err.kind, err.start, err.end),
+            (kind, *start, *end),
+            "{}",
+            literal
+        );
+    }
+
+    let tokens = tokenize("x = 1_000 + 'a'").unwrap();
+    assert_eq!(tokens[2].number_value(), Some(Ok(Int(1000))));
+    assert_eq!(tokens[4].number_value(), None);
+}
+
+#[test]
+fn test_tokenize_fstring_replacement_fields() {
+    use Token::*;
+    let op = |op: &str| OP(op.to_owned());
+    assert_eq!(
+        tokenize("f\"{d['k']!r:>{width}} {{x}}\"").unwrap(),
+        vec![
+            FStringStart("f\"".to_owned()),
+            op("{"),
+            Name("d".to_owned()),
+            op("["),
+            String("'k'".to_owned()),
+            op("]"),
+            op("!"),
+            Name("r".to_owned()),
+            op(":"),
+            FStringMiddle(">".to_owned()),
+            op("{"),
+            Name("width".to_owned()),
+            op("}"),
+            op("}"),
+            FStringMiddle(" {{x}}".to_owned()),
+            FStringEnd("\"".to_owned()),
+            NewLine,
+            EndMarker,
+        ]
+    );
+
+    // Debug specifiers, walrus operators in parentheses and format specs with colons
+    let tokens = tokenize("f'{x=}{(y:=1)}{t:%H:%M}'").unwrap();
+    assert_eq!(tokens[2..5], [Name("x".to_owned()), op("="), op("}")]);
+    assert_eq!(tokens[8], op(":="));
+    assert_eq!(tokens[15], FStringMiddle("%H:%M".to_owned()));
+
+    // Nested f-strings may reuse the quotes of the outer one
+    assert_eq!(
+        tokenize("f\"{f\"{x}\"}\"").unwrap()[..9],
+        [
+            FStringStart("f\"".to_owned()),
+            op("{"),
+            FStringStart("f\"".to_owned()),
+            op("{"),
+            Name("x".to_owned()),
+            op("}"),
+            FStringEnd("\"".to_owned()),
+            op("}"),
+            FStringEnd("\"".to_owned()),
+        ]
+    );
+
+    // A dictionary in a replacement field has its own braces and colons
+    let tokens = tokenize("f'{ {1: 2}[1] }'").unwrap();
+    assert_eq!(
+        tokens[2..7],
+        [
+            op("{"),
+            Number("1".to_owned()),
+            op(":"),
+            Number("2".to_owned()),
+            op("}")
+        ]
+    );
+
+    // Replacement fields of triple-quoted f-strings span lines
+    let tokens = tokenize_info("f'''a\n{x\n+ 1}'''\n").unwrap();
+    assert_eq!(tokens[4].token, NL);
+    assert_eq!(tokens[7].token, op("}"));
+    assert_eq!((tokens[7].start, tokens[7].end), ((3, 3), (3, 4)));
+    assert_eq!(tokens[7].line, "f'''a\n{x\n+ 1}'''\n");
+}
+
+#[test]
+fn test_fstring_errors() {
+    let err = tokenize("f'a}b'").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::SingleClosingBrace);
+    assert_eq!(err.start, (1, 4));
+
+    let err = tokenize("f'{x:>10'").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ExpectingClosingBrace);
+    assert_eq!(err.start, (1, 9));
+    // The quotes of the f-string in a replacement field end it, they do not open a string
+    let err = tokenize("f'{x'\ny = 1\n").unwrap_err();
+    assert_eq!(
+        err,
+        TokenizeError::at(ErrorKind::ExpectingClosingBrace, (1, 5))
+    );
+    let err = tokenize("f'''{x + '''a\n").unwrap_err();
+    assert_eq!(
+        err,
+        TokenizeError::at(ErrorKind::ExpectingClosingBrace, (1, 10))
+    );
+    let (tokens, errors) = tokenize_with_errors("f'{x'\ny = 1\n");
+    assert_eq!(
+        tokens[3..],
+        [
+            Token::FStringEnd("'".to_owned()),
+            Token::NewLine,
+            Token::Name("y".to_owned()),
+            Token::OP("=".to_owned()),
+            Token::Number("1".to_owned()),
+            Token::NewLine,
+            Token::EndMarker,
+        ]
+    );
+    assert_eq!(
+        errors,
+        [TokenizeError::at(ErrorKind::ExpectingClosingBrace, (1, 5))]
+    );
+
+    // An unterminated single-quoted f-string is given up on at the end of its line
+    let (tokens, errors) = tokenize_with_errors("f'{x\n$ = [1,\n2]\n");
+    assert_eq!(tokens[0], Token::ErrorToken("f'{x".to_owned()));
+    assert_eq!(tokens[1], Token::NewLine);
+    assert_eq!(tokens.len(), 12);
+    let kinds = errors.into_iter().map(|err| err.kind).collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        [
+            ErrorKind::UnterminatedFString,
+            ErrorKind::InvalidCharacter('$')
+        ]
+    );
+
+    let err = tokenize("x = f'{a}\ny = 1\n").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::UnterminatedFString);
+    assert_eq!((err.start, err.end), ((1, 5), (1, 10)));
+
+    let err = tokenize("f'''{a:{b}\n").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::UnterminatedTripleQuotedFString);
+    assert_eq!((err.start, err.end), ((1, 1), (2, 1)));
+
+    // The rest of the f-string is tokenized after an error in its literal text
+    let (tokens, errors) = tokenize_with_errors("f'a}b{x:y' + 1");
+    use Token::*;
+    assert_eq!(
+        tokens,
+        vec![
+            FStringStart("f'".to_owned()),
+            ErrorToken("a}".to_owned()),
+            FStringMiddle("b".to_owned()),
+            OP("{".to_owned()),
+            Name("x".to_owned()),
+            OP(":".to_owned()),
+            ErrorToken("y".to_owned()),
+            FStringEnd("'".to_owned()),
+            OP("+".to_owned()),
+            Number("1".to_owned()),
+            NewLine,
+            EndMarker,
+        ]
+    );
+    let kinds = errors.into_iter().map(|err| err.kind).collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        [
+            ErrorKind::SingleClosingBrace,
+            ErrorKind::ExpectingClosingBrace
+        ]
+    );
+
+    // Before Python 3.12 a whole f-string is one token, however it nests
+    let config = TokenizerConfig {
+        version: PythonVersion::new(3, 11),
+        ..TokenizerConfig::default()
+    };
+    let tokens =
+        Tokenizer::with_config("f'{x:{w}}{{' + 1", config).collect::<Result<Vec<_>>>().unwrap();
+    assert_eq!(tokens[0], Token::String("f'{x:{w}}{{'".to_owned()));
+    assert_eq!(tokens[1], Token::OP("+".to_owned()));
+    let tokens = Tokenizer::with_config("f'{d[\"k\"]:>{f\"{w}\"}}'", config)
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(
+        tokens[0],
+        Token::String("f'{d[\"k\"]:>{f\"{w}\"}}'".to_owned())
+    );
+    // and its replacement fields can not have what would end it or escape its text
+    let unsupported = ErrorKind::UnsupportedFeature {
+        feature: Feature::FStringTokens,
+        version: PythonVersion::new(3, 11),
+    };
+    let sources = [
+        "f\"{d[\"k\"]}\"",
+        "f'{f'{x}'}'",
+        "f'{f\"{x}'\"}'",
+        "f'{\"\\n\".join(x)}'",
+        "f'{f\"\\n{x}\"}'",
+        "f'''{x # comment\n}'''",
+        "f'''{x + \\\n y}'''",
+    ];
+    for source in sources.iter() {
+        let err = Tokenizer::with_config(source, config).collect::<Result<Vec<_>>>().unwrap_err();
+        assert_eq!(err.kind, unsupported, "{}", source);
+    }
+    let err =
+        Tokenizer::with_config("f'{d['k']}'", config).collect::<Result<Vec<_>>>().unwrap_err();
+    assert_eq!(err, TokenizeError::new(unsupported, (1, 6), (1, 9)));
+    for source in sources.iter() {
+        assert!(tokenize(source).is_ok(), "{}", source);
+    }
+}
+
+#[test]
+fn test_tokenize_template_strings() {
+    use Token::*;
+    assert_eq!(
+        tokenize("t'Hi {name!r:>{w}}' + Rt\"\\d{{x}}\"").unwrap(),
+        vec![
+            TStringStart("t'".to_owned()),
+            TStringMiddle("Hi ".to_owned()),
+            OP("{".to_owned()),
+            Name("name".to_owned()),
+            OP("!".to_owned()),
+            Name("r".to_owned()),
+            OP(":".to_owned()),
+            TStringMiddle(">".to_owned()),
+            OP("{".to_owned()),
+            Name("w".to_owned()),
+            OP("}".to_owned()),
+            OP("}".to_owned()),
+            TStringEnd("'".to_owned()),
+            OP("+".to_owned()),
+            TStringStart("Rt\"".to_owned()),
+            TStringMiddle("\\d{{x}}".to_owned()),
+            TStringEnd("\"".to_owned()),
+            NewLine,
+            EndMarker,
+        ]
+    );
+
+    // T-strings and f-strings nest in each other
+    let tokens = tokenize("t'{f'{x}'}'").unwrap();
+    assert_eq!(tokens[2], FStringStart("f'".to_owned()));
+    assert_eq!(tokens[8], TStringEnd("'".to_owned()));
+
+    for prefix in ["tr", "TR", "rT", "t", "T"].iter() {
+        let source = format!("{}'''a'''", prefix);
+        assert_eq!(
+            tokenize(&source).unwrap()[0],
+            TStringStart(format!("{}'''", prefix))
+        );
+    }
+    for prefix in ["ft", "tb", "bt", "ut"].iter() {
+        let err = tokenize(format!("{}'a'", prefix)).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidStringPrefix(prefix.to_string()));
+    }
+    assert_eq!(tokenize("t + tr").unwrap()[2], Name("tr".to_owned()));
+
+    let err = tokenize("t'{x}").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::UnterminatedTString);
+    let err = tokenize("t'''{x}").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::UnterminatedTripleQuotedTString);
+    assert_eq!(err.code(), "E0117");
+
+    // Template strings are new in Python 3.14
+    let config = TokenizerConfig {
+        version: PythonVersion::new(3, 13),
+        ..TokenizerConfig::default()
+    };
+    let err =
+        Tokenizer::with_config("x = t'{y}'\n", config).collect::<Result<Vec<_>>>().unwrap_err();
+    assert_eq!(
+        err.kind,
+        ErrorKind::UnsupportedFeature {
+            feature: Feature::TemplateStrings,
+            version: PythonVersion::new(3, 13),
+        }
+    );
+    assert_eq!((err.start, err.end), ((1, 5), (1, 6)));
+
+    assert_eq!(
+        decode_string("t'x'").unwrap_err().kind,
+        ErrorKind::FStringValue
+    );
+}
+
+#[test]
+fn test_tokenize_unicode_names() {
+    use Token::*;
+    // Names are made of XID_Start and XID_Continue characters, as PEP 3131 says
+    assert_eq!(
+        tokenize("café = a\u{b7}b + _x1 + \u{2118}").unwrap(),
+        vec![
+            Name("café".to_owned()),
+            OP("=".to_owned()),
+            Name("a\u{b7}b".to_owned()),
+            OP("+".to_owned()),
+            Name("_x1".to_owned()),
+            OP("+".to_owned()),
+            Name("\u{2118}".to_owned()),
+            NewLine,
+            EndMarker,
+        ]
+    );
+    // A name ends right before a quote
+    assert_eq!(
+        tokenize("abc'x'").unwrap()[..2],
+        [Name("abc".to_owned()), String("'x'".to_owned())]
+    );
+
+    let err = tokenize("x\u{200b} = 1").unwrap_err();
+    assert_eq!(
+        err,
+        TokenizeError::new(ErrorKind::InvalidCharacter('\u{200b}'), (1, 2), (1, 3))
+    );
+    let err = tokenize("print(\u{1f600})").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidCharacter('\u{1f600}'));
+    assert_eq!(err.start, (1, 7));
+    // A combining mark can go on a name but not start it
+    let err = tokenize("\u{301}a").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidCharacter('\u{301}'));
+    assert_eq!(
+        tokenize("a\u{301}").unwrap()[0],
+        Name("a\u{301}".to_owned())
+    );
+
+    let (tokens, errors) = tokenize_with_errors("a = \u{20ac}b\n");
+    assert_eq!(tokens[2], ErrorToken("\u{20ac}".to_owned()));
+    assert_eq!(tokens[3], Name("b".to_owned()));
+    assert_eq!(errors[0].kind, ErrorKind::InvalidCharacter('\u{20ac}'));
+
+    // Names compare by their NFKC form
+    let tokens = tokenize_ref("\u{210c} = H + a\u{301} + \u{e1}").unwrap();
+    assert_eq!(tokens[0].normalized_name().unwrap(), "H");
+    assert_eq!(tokens[0].normalized_name(), tokens[2].normalized_name());
+    assert_eq!(tokens[4].normalized_name().unwrap(), "\u{e1}");
+    assert!(matches!(
+        tokens[6].normalized_name(),
+        Some(std::borrow::Cow::Borrowed("\u{e1}"))
+    ));
+    assert_eq!(tokens[1].normalized_name(), None);
+}
+
+#[test]
+fn test_backslash_continuation() {
+    use Token::*;
+    // Joined lines make one logical line, whatever the indentation of the next line
+    let source = "if a and \\\n        b:\n    x = [1, \\\n2] + \\\r\n3\n";
+    assert_eq!(
+        tokenize(source).unwrap(),
+        vec![
+            Name("if".to_owned()),
+            Name("a".to_owned()),
+            Name("and".to_owned()),
+            Name("b".to_owned()),
+            OP(":".to_owned()),
+            NewLine,
+            Indent("    ".to_owned()),
+            Name("x".to_owned()),
+            OP("=".to_owned()),
+            OP("[".to_owned()),
+            Number("1".to_owned()),
+            OP(",".to_owned()),
+            Number("2".to_owned()),
+            OP("]".to_owned()),
+            OP("+".to_owned()),
+            Number("3".to_owned()),
+            NewLine,
+            Dedent,
+            EndMarker,
+        ]
+    );
+    let tokens = tokenize_info(source).unwrap();
+    assert_eq!(tokens[3].start, (2, 8));
+
+    // The same lines read one by one
+    let tokens = tokenize_reader(std::io::Cursor::new(source))
+        .map(|info| info.unwrap().token)
+        .collect::<Vec<_>>();
+    assert_eq!(tokens, tokenize(source).unwrap());
+
+    let err = tokenize("x = 1 \\ 2\n").unwrap_err();
+    assert_eq!(
+        err,
+        TokenizeError::new(
+            ErrorKind::UnexpectedCharacterAfterContinuation,
+            (1, 7),
+            (1, 8)
+        )
+    );
+    assert_eq!(err.code(), "E0502");
+    let err = tokenize("x = 1 + \\\n").unwrap_err();
+    assert_eq!(
+        err,
+        TokenizeError::new(ErrorKind::UnexpectedEof, (1, 9), (2, 1))
+    );
+    assert_eq!(
+        err.to_string(),
+        "error[E0503]: unexpected EOF while parsing at 1:9"
+    );
+    assert_eq!(
+        tokenize("x = \\").unwrap_err().kind,
+        ErrorKind::UnexpectedEof
+    );
+
+    let (tokens, errors) = tokenize_with_errors("x = 1 \\ 2\n");
+    assert_eq!(tokens[3], ErrorToken("\\".to_owned()));
+    assert_eq!(tokens[4], Number("2".to_owned()));
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn test_indentation_errors() {
+    let err = tokenize("if x:\n        a\n    b\n").unwrap_err();
+    assert_eq!(
+        err,
+        TokenizeError::new(ErrorKind::UnindentMismatch, (3, 1), (3, 5))
+    );
+    assert_eq!(
+        err.to_string(),
+        "error[E0302]: unindent does not match any outer indentation level at 3:1"
+    );
+
+    // A tab is as deep as 8 spaces, or as 1, and the two must agree
+    let err = tokenize("if x:\n\tif y:\n        a\n").unwrap_err();
+    assert_eq!(
+        err,
+        TokenizeError::new(ErrorKind::InconsistentTabs, (3, 1), (3, 9))
+    );
+    assert_eq!(err.code(), "E0303");
+    let err = tokenize("if x:\n        a\n\tb\n").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InconsistentTabs);
+    let err = tokenize("if x:\n    \tif y:\n\t    a\n").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InconsistentTabs);
+    assert!(tokenize("if x:\n\tif y:\n\t\ta\n\tb\n").is_ok());
+    assert!(tokenize("if x:\n  \tif y:\n  \t    a\n").is_ok());
+
+    // Only the line after a colon can be indented
+    let err = tokenize("x = 1\n    y = 2\n").unwrap_err();
+    assert_eq!(
+        err,
+        TokenizeError::new(ErrorKind::UnexpectedIndent, (2, 1), (2, 5))
+    );
+    assert_eq!(err.to_string(), "error[E0301]: unexpected indent at 2:1");
+    assert_eq!(
+        tokenize("  x\n").unwrap_err().kind,
+        ErrorKind::UnexpectedIndent
+    );
+    assert_eq!(
+        tokenize("class A: pass\n    b\n").unwrap_err().kind,
+        ErrorKind::UnexpectedIndent
+    );
+    assert!(tokenize("def f(a,\n      b):  # c\n\n    return (\n        a)\n").is_ok());
+
+    // The same checks hold when the lines are read one by one
+    let errors = tokenize_reader(std::io::Cursor::new("if x:\n    a\n  b\n"))
+        .filter_map(|info| info.err())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        vec![TokenizeError::new(
+            ErrorKind::UnindentMismatch,
+            (3, 1),
+            (3, 3)
+        )]
+    );
+    let tokens = tokenize_reader(std::io::Cursor::new("if x:\n    a\n"))
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(tokens[4].token, Token::Indent("    ".to_owned()));
+
+    // When recovering, the errors are recorded and the tokens stay the same
+    let (tokens, errors) = tokenize_with_errors("x\n  y\nif z:\n    a\n  b\n");
+    assert_eq!(
+        errors.iter().map(|err| err.kind.clone()).collect::<Vec<_>>(),
+        vec![ErrorKind::UnexpectedIndent, ErrorKind::UnindentMismatch]
+    );
+    assert_eq!(tokens[2], Token::Indent("  ".to_owned()));
+    assert_eq!(tokens.last(), Some(&Token::EndMarker));
+}
+
+#[test]
+fn test_bracket_errors() {
+    let err = tokenize("x = (1,\n  2]\n").unwrap_err();
+    assert_eq!(
+        err,
+        TokenizeError::new(
+            ErrorKind::MismatchedBracket {
+                opening: '(',
+                opening_start: (1, 5),
+                closing: ']',
+            },
+            (2, 4),
+            (2, 5)
+        )
+    );
+    assert_eq!(
+        err.to_string(),
+        "error[E0401]: closing parenthesis ']' does not match opening parenthesis '(' \
+         on line 1 at 2:4"
+    );
+
+    let err = tokenize("print(x))\n").unwrap_err();
+    assert_eq!(
+        err,
+        TokenizeError::new(ErrorKind::UnmatchedBracket(')'), (1, 9), (1, 10))
+    );
+    assert_eq!(err.message(), "unmatched ')'");
+
+    // The line of the open bracket is only named when it is an earlier one
+    let err = tokenize("x = (1]\n").unwrap_err();
+    assert_eq!(err.start, (1, 7));
+    assert_eq!(
+        err.message(),
+        "closing parenthesis ']' does not match opening parenthesis '('"
+    );
+
+    // An unclosed bracket is pointed at, the innermost one first
+    let err = tokenize("foo(a, [b,\n  c\n").unwrap_err();
+    assert_eq!(
+        err,
+        TokenizeError::new(ErrorKind::UnclosedBracket('['), (1, 8), (1, 9))
+    );
+    assert_eq!(err.to_string(), "error[E0403]: '[' was never closed at 1:8");
+    let err = tokenize_reader(std::io::Cursor::new("x = 1\ny = {\n  1: 2,\n"))
+        .find_map(|info| info.err())
+        .unwrap();
+    assert_eq!(
+        err,
+        TokenizeError::new(ErrorKind::UnclosedBracket('{'), (2, 5), (2, 6))
+    );
+
+    // Brackets in the replacement fields of f-strings are checked too
+    let err = tokenize("f'{x)}'").unwrap_err();
+    assert_eq!(err.code(), "E0401");
+    assert!(tokenize("f'{(x)}' + [f'{ {1: 2}[1] }']").is_ok());
+
+    let (tokens, errors) = tokenize_with_errors("x = [1, 2)\ny = (3\n");
+    assert_eq!(tokens[6], Token::ErrorToken(")".to_owned()));
+    assert_eq!(tokens[8], Token::Name("y".to_owned()));
+    assert_eq!(
+        errors.iter().map(TokenizeError::code).collect::<Vec<_>>(),
+        vec!["E0401", "E0403"]
+    );
+    assert_eq!(tokens.last(), Some(&Token::EndMarker));
+}
+
+#[test]
+fn test_operator_kinds() {
+    // The operators of Python's `token.EXACT_TOKEN_TYPES`
+    let operators = [
+        "!", "!=", "%", "%=", "&", "&=", "(", ")", "*", "**", "**=", "*=", "+", "+=", ",", "-",
+        "-=", "->", ".", "...", "/", "//", "//=", "/=", ":", ":=", ";", "<", "<<", "<<=", "<=",
+        "=", "==", ">", ">=", ">>", ">>=", "@", "@=", "[", "]", "^", "^=", "{", "|", "|=", "}",
+        "~",
+    ];
+    for operator in operators.iter() {
+        let kind = OperatorKind::from_operator(operator).unwrap();
+        assert_eq!(kind.as_str(), *operator);
+        // Each operator is a token of its own, closing brackets after the ones they close
+        let opening = match *operator {
+            ")" => "(",
+            "]" => "[",
+            "}" => "{",
+            _ => "",
+        };
+        let source = format!("{}a {} b", opening, operator);
+        let mut tokenizer = Tokenizer::new(&source);
+        while tokenizer.next_ref() != Some(Ok(TokenRef::Name("a"))) {}
+        let token = tokenizer.next_ref().unwrap().unwrap();
+        assert_eq!(token, TokenRef::OP(operator), "{}", source);
+        assert_eq!(token.operator_kind(), Some(kind));
+    }
+    assert_eq!(OperatorKind::ColonEqual.name(), "COLONEQUAL");
+    assert_eq!(OperatorKind::from_operator("<>"), None);
+
+    // The longest operator is taken
+    let tokens = tokenize("a//=b>>=c<<=d**=e...f..g::h<>i").unwrap();
+    let kinds = tokens.iter().filter_map(Token::operator_kind).collect::<Vec<_>>();
+    use OperatorKind::*;
+    assert_eq!(
+        kinds,
+        vec![
+            DoubleSlashEqual,
+            RightShiftEqual,
+            LeftShiftEqual,
+            DoubleStarEqual,
+            Ellipsis,
+            Dot,
+            Dot,
+            Colon,
+            Colon,
+            Less,
+            Greater,
+        ]
+    );
+    assert_eq!(
+        tokenize("x[...5]").unwrap()[3],
+        Token::Number("5".to_owned())
+    );
+    assert_eq!(tokenize("1").unwrap()[0].operator_kind(), None);
+
+    for c in ['$', '?', '`'].iter() {
+        let err = tokenize(format!("a {} b", c)).unwrap_err();
+        assert_eq!(
+            err,
+            TokenizeError::new(ErrorKind::InvalidCharacter(*c), (1, 3), (1, 4))
+        );
+    }
+    // A lone `!` is only an operator since f-strings have tokens of their own
+    let config = TokenizerConfig {
+        version: PythonVersion::new(3, 11),
+        ..TokenizerConfig::default()
+    };
+    let err = Tokenizer::with_config("!x", config).collect::<Result<Vec<_>>>().unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidCharacter('!'));
+    assert!(Tokenizer::with_config("a != b", config).collect::<Result<Vec<_>>>().is_ok());
+    // It always separates the conversion of a replacement field
+    for source in ["f'{a!r}'", "f'{a!s:>10}'", "f'{a!a:>{w}}'"].iter() {
+        for minor in [8, 11].iter() {
+            let config = TokenizerConfig {
+                version: PythonVersion::new(3, *minor),
+                ..TokenizerConfig::default()
+            };
+            let tokens = Tokenizer::with_config(source, config).collect::<Result<Vec<_>>>();
+            assert_eq!(tokens.unwrap()[0], Token::String(source.to_string()));
+        }
+    }
+}
+
+#[test]
+fn test_classify_names() {
+    let latest = PythonVersion::LATEST;
+    let tokens = tokenize("async def f(_, match):\n    type = not await None\n").unwrap();
+    let kinds = tokens.iter().filter_map(|token| token.name_kind(latest)).collect::<Vec<_>>();
+    use NameKind::*;
+    assert_eq!(
+        kinds,
+        vec![
+            Keyword,
+            Keyword,
+            Identifier,
+            SoftKeyword,
+            SoftKeyword,
+            SoftKeyword,
+            Keyword,
+            Keyword,
+            Keyword,
+        ]
+    );
+    assert_eq!(tokens[1].name_kind(latest), Some(Keyword));
+    assert_eq!(tokens[3].name_kind(latest), None);
+    assert_eq!(
+        tokenize_ref("case").unwrap()[0].name_kind(latest),
+        Some(SoftKeyword)
+    );
+
+    // The keywords follow the version of Python
+    assert_eq!(classify_name("async", PythonVersion::new(3, 6)), Identifier);
+    assert_eq!(classify_name("async", PythonVersion::new(3, 7)), Keyword);
+    assert_eq!(classify_name("match", PythonVersion::new(3, 9)), Identifier);
+    assert_eq!(
+        classify_name("match", PythonVersion::new(3, 10)),
+        SoftKeyword
+    );
+    assert_eq!(classify_name("type", PythonVersion::new(3, 11)), Identifier);
+    assert_eq!(
+        classify_name("type", PythonVersion::new(3, 12)),
+        SoftKeyword
+    );
+    assert_eq!(
+        classify_name("__peg_parser__", PythonVersion::new(3, 9)),
+        Keyword
+    );
+    assert_eq!(
+        classify_name("__peg_parser__", PythonVersion::new(3, 10)),
+        Identifier
+    );
+    // Keywords are written in ASCII and are case sensitive
+    assert_eq!(
+        classify_name("\u{ff44}\u{ff45}\u{ff46}", latest),
+        Identifier
+    );
+    assert_eq!(classify_name("None", latest), Keyword);
+    assert_eq!(classify_name("none", latest), Identifier);
+
+    let kwlist = keywords(latest);
+    assert_eq!(kwlist.len(), 35);
+    assert_eq!(kwlist[..4], ["False", "None", "True", "and"]);
+    let mut sorted = kwlist.clone();
+    sorted.sort_unstable();
+    assert_eq!(sorted, kwlist);
+    assert_eq!(keywords(PythonVersion::new(3, 9)).len(), 36);
+    assert_eq!(keywords(PythonVersion::new(3, 6)).len(), 33);
+    assert_eq!(
+        soft_keywords(PythonVersion::new(3, 10)),
+        vec!["_", "case", "match"]
+    );
+    assert_eq!(soft_keywords(latest), vec!["_", "case", "match", "type"]);
+}
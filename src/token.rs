@@ -1,11 +1,18 @@
+use std::borrow::Cow;
 use std::fmt::Debug;
 
+use crate::collectors::normalize_name;
+use crate::{
+    classify_name, decode_number, decode_string, NameKind, NumberValue, OperatorKind,
+    PythonVersion, Result, StringValue,
+};
+
 /// An enumeration of Python tokens.
 ///
 /// # Example
 ///
 /// ```rust
-/// use tokenizer_py::{tokenize, Token};
+/// use tokenizer_py::{tokenize, NumberValue, Token, TokenizeError};
 /// struct BinaryExp{
 ///     left: Token, center: Token,right: Token,
 /// }
@@ -14,13 +21,14 @@ use std::fmt::Debug;
 ///     fn new(left: Token, center: Token, right: Token) -> Self {
 ///         BinaryExp { left, center, right}
 ///     }
-///     fn execute(&self) -> Result<isize, <isize as std::str::FromStr>::Err> {
-///         use Token::{Number, OP};
-///         match (&self.left, &self.center, &self.right) {
-///             (Number(ref left), OP(ref op), Number(ref right)) => {
-///               let (left, right) = (
-///                 left.parse::<isize>()?, right.parse::<isize>()?
-///               );
+///     fn execute(&self) -> Result<i128, TokenizeError> {
+///         use NumberValue::Int;
+///         match (self.left.number_value(), &self.center, self.right.number_value()) {
+///             (Some(left), Token::OP(ref op), Some(right)) => {
+///               let (left, right) = match (left?, right?) {
+///                 (Int(left), Int(right)) => (left, right),
+///                 _ => panic!("Not integers"),
+///               };
 ///               match op.as_str() {
 ///                 "+" => Ok(left + right),
 ///                 "-" => Ok(left - right),
@@ -34,7 +42,7 @@ use std::fmt::Debug;
 ///         }
 ///     }
 /// }
-/// let mut tokens = tokenize("10 + 10").unwrap();
+/// let mut tokens = tokenize("1_000 + 0x10").unwrap();
 /// let _ = tokens.pop(); // remove Token::EndMarker
 /// let _ = tokens.pop(); // remove Token::NewLine
 ///
@@ -44,7 +52,7 @@ use std::fmt::Debug;
 ///     tokens.pop().unwrap()
 /// );
 ///
-/// assert_eq!(binexp.execute(), Ok(20));
+/// assert_eq!(binexp.execute(), Ok(1016));
 /// ```
 #[derive(Debug, PartialEq, Eq)]
 pub enum Token {
@@ -57,6 +65,8 @@ pub enum Token {
     /// A string token, such as a single or double-quoted string.
     String(String),
     /// A newiter token, indicating a new iter in the source code.
+    ///
+    /// Which newline characters it stands for is only kept by `TokenRef::NewLine`.
     NewLine,
     /// An operator token, such as an arithmetic or comparison operator.
     OP(String),
@@ -67,11 +77,375 @@ pub enum Token {
     /// A comment token, such as a single-iter or multi-iter comment.
     Comment(String),
     /// A token indicating a new iter, for compatibility with the original tokenizer.
+    ///
+    /// Which newline characters it stands for is only kept by `TokenRef::NL`.
     NL,
     /// A token indicating the start of a formatted string.
     FStringStart(String),
-    /// A token indicating the middle of a formatted string.
+    /// A token indicating the middle of a formatted string: a piece of its literal text,
+    /// where doubled braces are kept as they are, or the format spec of a replacement field.
     FStringMiddle(String),
     /// A token indicating the end of a formatted string.
     FStringEnd(String),
+    /// A token indicating the start of a template string.
+    TStringStart(String),
+    /// A token indicating the middle of a template string,
+    /// made like the middle of a formatted string.
+    TStringMiddle(String),
+    /// A token indicating the end of a template string.
+    TStringEnd(String),
+    /// The encoding used to decode the source, always the first token of `tokenize_bytes`.
+    Encoding(String),
+    /// Spaces, tabs and form feeds between tokens, only produced in lossless mode.
+    Whitespace(String),
+    /// A backslash with the newline it joins, only produced in lossless mode.
+    Continuation(String),
+    /// Text that could not be tokenized, only produced when recovering from errors.
+    ErrorToken(String),
+}
+
+impl Token {
+    /// Decodes the value of a `Token::String`, or returns `None` for other tokens.
+    ///
+    /// See [`decode_string`] for how the value is made.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tokenizer_py::{tokenize, StringValue};
+    ///
+    /// let tokens = tokenize(r"b'\x00abc'").unwrap();
+    ///
+    /// assert_eq!(
+    ///     tokens[0].string_value(),
+    ///     Some(Ok(StringValue::Bytes(b"\x00abc".to_vec())))
+    /// );
+    /// assert_eq!(tokens[1].string_value(), None);
+    /// ```
+    pub fn string_value(&self) -> Option<Result<StringValue>> {
+        match self {
+            Token::String(literal) => Some(decode_string(literal)),
+            _ => None,
+        }
+    }
+
+    /// Decodes the value of a `Token::Number`, or returns `None` for other tokens.
+    ///
+    /// See [`decode_number`] for how the value is made.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tokenizer_py::{tokenize, NumberValue};
+    ///
+    /// let tokens = tokenize("0b1010 + 1.5j").unwrap();
+    ///
+    /// assert_eq!(tokens[0].number_value(), Some(Ok(NumberValue::Int(10))));
+    /// assert_eq!(tokens[2].number_value(), Some(Ok(NumberValue::Imaginary(1.5))));
+    /// assert_eq!(tokens[1].number_value(), None);
+    /// ```
+    pub fn number_value(&self) -> Option<Result<NumberValue>> {
+        match self {
+            Token::Number(literal) => Some(decode_number(literal)),
+            _ => None,
+        }
+    }
+
+    /// Returns the NFKC form of a `Token::Name`, or `None` for other tokens.
+    ///
+    /// Python normalizes names this way, so names that are written differently,
+    /// such as `ﬁle` and `file`, are the same name when their forms are equal.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tokenizer_py::tokenize;
+    ///
+    /// let tokens = tokenize("\u{fb01}le = file").unwrap();
+    ///
+    /// assert_eq!(tokens[0].normalized_name().unwrap(), "file");
+    /// assert_eq!(tokens[0].normalized_name(), tokens[2].normalized_name());
+    /// assert_eq!(tokens[1].normalized_name(), None);
+    /// ```
+    pub fn normalized_name(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Token::Name(name) => Some(normalize_name(name)),
+            _ => None,
+        }
+    }
+
+    /// Returns the kind of a `Token::OP`, or `None` for other tokens,
+    /// like the `exact_type` of a token in Python's `tokenize`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tokenizer_py::{tokenize, OperatorKind};
+    ///
+    /// let tokens = tokenize("x **= ...").unwrap();
+    ///
+    /// assert_eq!(tokens[1].operator_kind(), Some(OperatorKind::DoubleStarEqual));
+    /// assert_eq!(tokens[2].operator_kind(), Some(OperatorKind::Ellipsis));
+    /// assert_eq!(tokens[0].operator_kind(), None);
+    /// ```
+    pub fn operator_kind(&self) -> Option<OperatorKind> {
+        match self {
+            Token::OP(operator) => OperatorKind::from_operator(operator),
+            _ => None,
+        }
+    }
+
+    /// Tells whether a `Token::Name` is a keyword, a soft keyword or an identifier
+    /// in the given version of Python, or returns `None` for other tokens.
+    ///
+    /// See [`classify_name`] for how names are told apart.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tokenizer_py::{tokenize, NameKind, PythonVersion};
+    ///
+    /// let tokens = tokenize("match = lambda: type").unwrap();
+    /// let kinds = tokens
+    ///     .iter()
+    ///     .filter_map(|token| token.name_kind(PythonVersion::new(3, 11)))
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     kinds,
+    ///     vec![NameKind::SoftKeyword, NameKind::Keyword, NameKind::Identifier]
+    /// );
+    /// ```
+    pub fn name_kind(&self, version: PythonVersion) -> Option<NameKind> {
+        match self {
+            Token::Name(name) => Some(classify_name(name, version)),
+            _ => None,
+        }
+    }
+}
+
+/// A token together with its location in the source text,
+/// mirroring Python's `tokenize.TokenInfo`.
+///
+/// Lines are numbered from 1 and columns from 0, as in CPython,
+/// and `end` points just past the last character of the token.
+///
+/// # Example
+///
+/// ```rust
+/// use tokenizer_py::{tokenize_info, Token, TokenInfo};
+///
+/// let tokens = tokenize_info("x = 1\n").unwrap();
+///
+/// assert_eq!(tokens[1], TokenInfo {
+///     token: Token::OP("=".to_string()),
+///     start: (1, 2),
+///     end: (1, 3),
+///     line: "x = 1\n".to_string(),
+/// });
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct TokenInfo {
+    /// The token itself.
+    pub token: Token,
+    /// The `(line, column)` where the token begins.
+    pub start: (usize, usize),
+    /// The `(line, column)` where the token ends.
+    pub end: (usize, usize),
+    /// The physical line (or lines) the token was found on,
+    /// starting with the lines before it that only hold a backslash continuation.
+    pub line: String,
+}
+
+impl TokenInfo {
+    /// Creates a new `TokenInfo` from positions reported by `PeekableCharTracker`,
+    /// whose columns start at 1. The `line` is filled in later.
+    #[inline]
+    pub(crate) fn new(token: Token, start: (usize, usize), end: (usize, usize)) -> Self {
+        TokenInfo {
+            token,
+            start: (start.0, start.1 - 1),
+            end: (end.0, end.1 - 1),
+            line: String::new(),
+        }
+    }
+}
+
+/// A borrowed counterpart of [`Token`] whose text points into the tokenized source.
+///
+/// Tokenizing into `TokenRef` does not allocate per token,
+/// and a `TokenRef` can be turned into an owned [`Token`] with `Token::from`.
+///
+/// # Example
+///
+/// ```rust
+/// use tokenizer_py::{tokenize_ref, Token, TokenRef};
+///
+/// let source = "print('hi')";
+/// let tokens = tokenize_ref(source).unwrap();
+///
+/// assert_eq!(tokens[2], TokenRef::String("'hi'"));
+/// assert_eq!(Token::from(tokens[0]), Token::Name("print".to_string()));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenRef<'a> {
+    /// Indicates the end of the text.
+    EndMarker,
+    /// A name token, such as a function, variable, or special name.
+    Name(&'a str),
+    /// A number token, such as a literal integer or floating-point number.
+    Number(&'a str),
+    /// A string token, such as a single or double-quoted string.
+    String(&'a str),
+    /// A newline token, indicating the end of a logical line.
+    ///
+    /// It holds the newline characters as written, `"\n"`, `"\r\n"` or `"\r"`,
+    /// or nothing when the text does not end with a newline.
+    NewLine(&'a str),
+    /// An operator token, such as an arithmetic or comparison operator.
+    OP(&'a str),
+    /// An indent token, indicating that a block of code is being indented.
+    Indent(&'a str),
+    /// A dedent token, indicating that a block of code is being dedented.
+    Dedent,
+    /// A comment token.
+    Comment(&'a str),
+    /// A token indicating a new line that does not end a logical line.
+    NL(&'a str),
+    /// A token indicating the start of a formatted string.
+    FStringStart(&'a str),
+    /// A token indicating the middle of a formatted string.
+    FStringMiddle(&'a str),
+    /// A token indicating the end of a formatted string.
+    FStringEnd(&'a str),
+    /// A token indicating the start of a template string.
+    TStringStart(&'a str),
+    /// A token indicating the middle of a template string.
+    TStringMiddle(&'a str),
+    /// A token indicating the end of a template string.
+    TStringEnd(&'a str),
+    /// Spaces, tabs and form feeds between tokens, only produced in lossless mode.
+    Whitespace(&'a str),
+    /// A backslash with the newline it joins, only produced in lossless mode.
+    Continuation(&'a str),
+    /// Text that could not be tokenized, only produced when recovering from errors.
+    ErrorToken(&'a str),
+}
+
+impl<'a> TokenRef<'a> {
+    /// Returns the source text of the token.
+    ///
+    /// In lossless mode the texts of all tokens add up to the whole source.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tokenizer_py::{TokenRef, Tokenizer};
+    ///
+    /// let mut tokenizer = Tokenizer::new("x  = 1\r\n").lossless(true);
+    ///
+    /// assert_eq!(tokenizer.next_ref().unwrap().unwrap().as_str(), "x");
+    /// assert_eq!(tokenizer.next_ref().unwrap().unwrap(), TokenRef::Whitespace("  "));
+    /// ```
+    pub fn as_str(&self) -> &'a str {
+        match *self {
+            TokenRef::Name(text)
+            | TokenRef::Number(text)
+            | TokenRef::String(text)
+            | TokenRef::NewLine(text)
+            | TokenRef::OP(text)
+            | TokenRef::Indent(text)
+            | TokenRef::Comment(text)
+            | TokenRef::NL(text)
+            | TokenRef::FStringStart(text)
+            | TokenRef::FStringMiddle(text)
+            | TokenRef::FStringEnd(text)
+            | TokenRef::TStringStart(text)
+            | TokenRef::TStringMiddle(text)
+            | TokenRef::TStringEnd(text)
+            | TokenRef::Whitespace(text)
+            | TokenRef::Continuation(text)
+            | TokenRef::ErrorToken(text) => text,
+            TokenRef::EndMarker | TokenRef::Dedent => "",
+        }
+    }
+
+    /// Decodes the value of a `TokenRef::String`, or returns `None` for other tokens.
+    ///
+    /// See [`decode_string`] for how the value is made.
+    pub fn string_value(&self) -> Option<Result<StringValue>> {
+        match *self {
+            TokenRef::String(literal) => Some(decode_string(literal)),
+            _ => None,
+        }
+    }
+
+    /// Decodes the value of a `TokenRef::Number`, or returns `None` for other tokens.
+    ///
+    /// See [`decode_number`] for how the value is made.
+    pub fn number_value(&self) -> Option<Result<NumberValue>> {
+        match *self {
+            TokenRef::Number(literal) => Some(decode_number(literal)),
+            _ => None,
+        }
+    }
+
+    /// Returns the NFKC form of a `TokenRef::Name`, or `None` for other tokens.
+    ///
+    /// See [`Token::normalized_name`] for why names are normalized.
+    pub fn normalized_name(&self) -> Option<Cow<'a, str>> {
+        match *self {
+            TokenRef::Name(name) => Some(normalize_name(name)),
+            _ => None,
+        }
+    }
+
+    /// Returns the kind of a `TokenRef::OP`, or `None` for other tokens.
+    ///
+    /// See [`Token::operator_kind`].
+    pub fn operator_kind(&self) -> Option<OperatorKind> {
+        match *self {
+            TokenRef::OP(operator) => OperatorKind::from_operator(operator),
+            _ => None,
+        }
+    }
+
+    /// Tells whether a `TokenRef::Name` is a keyword, a soft keyword or an identifier
+    /// in the given version of Python, or returns `None` for other tokens.
+    ///
+    /// See [`classify_name`] for how names are told apart.
+    pub fn name_kind(&self, version: PythonVersion) -> Option<NameKind> {
+        match *self {
+            TokenRef::Name(name) => Some(classify_name(name, version)),
+            _ => None,
+        }
+    }
+}
+
+impl From<TokenRef<'_>> for Token {
+    /// Copies the text of a borrowed token into an owned `Token`,
+    /// leaving out the newline characters of `NewLine` and `NL` tokens.
+    fn from(token: TokenRef<'_>) -> Self {
+        match token {
+            TokenRef::EndMarker => Token::EndMarker,
+            TokenRef::Name(name) => Token::Name(name.to_owned()),
+            TokenRef::Number(number) => Token::Number(number.to_owned()),
+            TokenRef::String(string) => Token::String(string.to_owned()),
+            TokenRef::NewLine(_) => Token::NewLine,
+            TokenRef::OP(op) => Token::OP(op.to_owned()),
+            TokenRef::Indent(indent) => Token::Indent(indent.to_owned()),
+            TokenRef::Dedent => Token::Dedent,
+            TokenRef::Comment(comment) => Token::Comment(comment.to_owned()),
+            TokenRef::NL(_) => Token::NL,
+            TokenRef::FStringStart(start) => Token::FStringStart(start.to_owned()),
+            TokenRef::FStringMiddle(middle) => Token::FStringMiddle(middle.to_owned()),
+            TokenRef::FStringEnd(end) => Token::FStringEnd(end.to_owned()),
+            TokenRef::TStringStart(start) => Token::TStringStart(start.to_owned()),
+            TokenRef::TStringMiddle(middle) => Token::TStringMiddle(middle.to_owned()),
+            TokenRef::TStringEnd(end) => Token::TStringEnd(end.to_owned()),
+            TokenRef::Whitespace(space) => Token::Whitespace(space.to_owned()),
+            TokenRef::Continuation(backslash) => Token::Continuation(backslash.to_owned()),
+            TokenRef::ErrorToken(text) => Token::ErrorToken(text.to_owned()),
+        }
+    }
 }
@@ -0,0 +1,1070 @@
+use std::collections::VecDeque;
+
+use crate::collectors::{
+    collect_comment, collect_fstring_middle, collect_indent, collect_name, collect_number,
+    collect_operator, collect_string, collect_whitespace, is_name_start, unterminated, Indentation,
+};
+use crate::{
+    privat::PeekableCharTracker, ErrorKind, Feature, Result, Token, TokenInfo, TokenRef,
+    TokenizeError, TokenizeWarning, TokenizerConfig, OPERATORS,
+};
+
+/// The prefixes of f-strings, in any case.
+const FSTRING_PREFIXES: [&str; 3] = ["f", "fr", "rf"];
+
+/// The prefixes of t-strings, in any case.
+const TSTRING_PREFIXES: [&str; 3] = ["t", "tr", "rt"];
+
+/// A borrowed token with everything needed to build its `TokenInfo`.
+pub(crate) struct Spanned<'a> {
+    pub token: TokenRef<'a>,
+    /// The start position as reported by `PeekableCharTracker`.
+    pub start: (usize, usize),
+    /// The end position as reported by `PeekableCharTracker`.
+    pub end: (usize, usize),
+    /// The byte offset of the first line the token is on.
+    pub line_offset: usize,
+    /// The byte offset just past the token.
+    pub end_offset: usize,
+}
+
+/// What a piece of a larger text takes over from the pieces before it.
+#[derive(Clone, Debug)]
+pub(crate) struct LineContext {
+    pub ind_stack: Vec<Indentation>,
+    pub brackets_stack: Vec<(char, (usize, usize))>,
+    pub block_start: bool,
+}
+
+impl Default for LineContext {
+    fn default() -> Self {
+        LineContext {
+            ind_stack: vec![Indentation::default()],
+            brackets_stack: Vec::new(),
+            block_start: false,
+        }
+    }
+}
+
+/// An f-string or a t-string that is being tokenized.
+///
+/// T-strings are tokenized like f-strings, only with tokens of their own.
+/// F-strings can nest in the replacement fields of other f-strings,
+/// even with the same quotes, so the tokenizer keeps a stack of them.
+struct FStringMode<'a> {
+    /// The quotes that end the f-string.
+    quote: &'a str,
+    /// Whether the backslashes of the f-string do not start escape sequences.
+    raw: bool,
+    /// Whether the f-string is a t-string.
+    template: bool,
+    /// The length of the brackets stack outside of the f-string.
+    bracket_depth: usize,
+    /// The open replacement fields, innermost last,
+    /// each telling if the tokenizer has reached its format spec.
+    fields: Vec<bool>,
+    start: (usize, usize),
+    start_offset: usize,
+    line_offset: usize,
+    /// The iterator at the start of the f-string, for going back to its line.
+    start_iter: PeekableCharTracker<'a>,
+}
+
+impl FStringMode<'_> {
+    /// Returns the length of the brackets stack at the top level of the innermost field.
+    fn field_depth(&self) -> usize {
+        self.bracket_depth + self.fields.len()
+    }
+}
+
+/// A lazy tokenizer that yields tokens one at a time.
+///
+/// Unlike [`tokenize`](crate::tokenize), the `Tokenizer` does not build the whole
+/// vector of tokens up front, so large sources can be processed with bounded memory
+/// and the consumer can stop at any point. After an error is returned the
+/// tokenizer yields nothing more, unless it is [recovering](Tokenizer::recover) from errors.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{Token, Tokenizer};
+///
+/// let mut tokenizer = Tokenizer::new("x = 1\ny = 2\n");
+///
+/// assert_eq!(tokenizer.next(), Some(Ok(Token::Name("x".to_string()))));
+/// assert_eq!(tokenizer.next(), Some(Ok(Token::OP("=".to_string()))));
+///
+/// let names = Tokenizer::new("x = 1\ny = 2\n")
+///     .filter_map(|token| match token {
+///         Ok(Token::Name(name)) => Some(name),
+///         _ => None,
+///     })
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(names, vec!["x", "y"]);
+/// ```
+pub struct Tokenizer<'a> {
+    iter: PeekableCharTracker<'a>,
+    ind_stack: Vec<Indentation>,
+    /// The open brackets, innermost last, with the positions they were opened at.
+    brackets_stack: Vec<(char, (usize, usize))>,
+    pending: VecDeque<Spanned<'a>>,
+    fstrings: Vec<FStringMode<'a>>,
+    at_line_start: bool,
+    /// Whether the last logical line ended with a colon, so that the next one can be indented.
+    block_start: bool,
+    blank_line: bool,
+    /// The byte offset of the first of the lines before the current one that only hold
+    /// a backslash continuation, which the `line` of the next token starts with.
+    joined_line: Option<usize>,
+    partial: bool,
+    config: TokenizerConfig,
+    errors: Vec<TokenizeError>,
+    warnings: Vec<TokenizeWarning>,
+    finished: bool,
+}
+
+/// Returns the quotes that the text starts with, three of a kind or a single one.
+fn quote_at(text: &str) -> &str {
+    if text.starts_with("'''") || text.starts_with("\"\"\"") {
+        &text[..3]
+    } else {
+        &text[..1]
+    }
+}
+
+/// Collects a string that starts at `start_offset`, which may be in the code
+/// of a replacement field of an f-string ending with `fstring_quote`.
+///
+/// A string in the quotes of that f-string that is not closed is taken
+/// to be the end of the f-string, which is an error that leaves the iterator
+/// at those quotes.
+fn collect_field_string<'a>(
+    iter: &mut PeekableCharTracker<'a>,
+    start_offset: usize,
+    fstring_quote: Option<&str>,
+    warnings: &mut Vec<TokenizeWarning>,
+) -> Result<&'a str> {
+    let before = iter.clone();
+    let warnings_len = warnings.len();
+    match collect_string(iter, start_offset, warnings) {
+        Err(err)
+            if matches!(
+                err.kind,
+                ErrorKind::UnterminatedString | ErrorKind::UnterminatedTripleQuotedString
+            ) =>
+        {
+            let mut quote_iter = before;
+            while quote_iter.next_if(|c| *c != '\'' && *c != '"').is_some() {}
+            if fstring_quote != Some(quote_at(&iter.src()[quote_iter.offset()..])) {
+                return Err(err);
+            }
+            *iter = quote_iter;
+            warnings.truncate(warnings_len);
+            Err(TokenizeError::at(
+                ErrorKind::ExpectingClosingBrace,
+                iter.pos(),
+            ))
+        }
+        result => result,
+    }
+}
+
+/// Checks if the character ends a line, alone or followed by `\n` for `\r`.
+fn is_line_ending(c: char) -> bool {
+    c == '\n' || c == '\r'
+}
+
+impl<'a> Tokenizer<'a> {
+    /// Creates a new `Tokenizer` over the given text.
+    pub fn new(text: &'a str) -> Self {
+        Tokenizer::with_config(text, TokenizerConfig::default())
+    }
+
+    /// Creates a new `Tokenizer` over the given text with the given settings.
+    pub fn with_config(text: &'a str, config: TokenizerConfig) -> Self {
+        Tokenizer {
+            iter: PeekableCharTracker::new(text),
+            // A stack of indentation sizes,
+            // the initial zero will be retained until the end of the text
+            ind_stack: vec![Indentation::default()],
+            brackets_stack: Vec::new(),
+            pending: VecDeque::new(),
+            fstrings: Vec::new(),
+            at_line_start: true,
+            block_start: false,
+            blank_line: true,
+            joined_line: None,
+            partial: false,
+            config,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// Turns the lossless mode on or off.
+    ///
+    /// In lossless mode the tokenizer also yields `Token::Whitespace` and
+    /// `Token::Continuation` tokens for the text between other tokens,
+    /// so that the texts of all the tokens from [`next_ref`](Tokenizer::next_ref)
+    /// add up to the tokenized source byte for byte.
+    /// Only those borrowed tokens are lossless: the owned `Token::NewLine` and `Token::NL`
+    /// of the `Iterator` impl and of [`next_info`](Tokenizer::next_info) do not tell
+    /// `\r\n` from `\n`, see [`tokenize_lossless`](crate::tokenize_lossless).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokenizer_py::Tokenizer;
+    ///
+    /// let source = "if x:\r\n\ty = [1,  \\\n  2]\r\n";
+    /// let mut tokenizer = Tokenizer::new(source).lossless(true);
+    /// let mut text = String::new();
+    /// while let Some(token) = tokenizer.next_ref() {
+    ///     text.push_str(token.unwrap().as_str());
+    /// }
+    ///
+    /// assert_eq!(text, source);
+    /// ```
+    pub fn lossless(mut self, lossless: bool) -> Self {
+        self.config.lossless = lossless;
+        self
+    }
+
+    /// Turns the error recovery mode on or off.
+    ///
+    /// When recovering, the tokenizer does not stop at the first error.
+    /// It records the error, yields a `Token::ErrorToken` covering the text
+    /// it could not tokenize and carries on up to `Token::EndMarker`.
+    /// The recorded errors are returned by [`errors`](Tokenizer::errors).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokenizer_py::{Token, Tokenizer};
+    ///
+    /// let mut tokenizer = Tokenizer::new("s = 'abc\nx = 1_\n").recover(true);
+    /// let tokens = tokenizer.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+    ///
+    /// assert_eq!(tokens[2], Token::ErrorToken("'abc".to_string()));
+    /// assert_eq!(tokens[6], Token::ErrorToken("1_".to_string()));
+    /// assert_eq!(tokens.last(), Some(&Token::EndMarker));
+    /// assert_eq!(tokenizer.errors().len(), 2);
+    /// ```
+    pub fn recover(mut self, recover: bool) -> Self {
+        self.config.recover = recover;
+        self
+    }
+
+    /// Returns the errors recorded so far in the error recovery mode.
+    pub fn errors(&self) -> &[TokenizeError] {
+        &self.errors
+    }
+
+    /// Consumes the tokenizer, returning the errors it recorded.
+    pub fn into_errors(self) -> Vec<TokenizeError> {
+        self.errors
+    }
+
+    /// Returns the warnings found so far, such as invalid escape sequences in strings.
+    ///
+    /// Warnings never stop the tokenizer, whether it recovers from errors or not.
+    pub fn warnings(&self) -> &[TokenizeWarning] {
+        &self.warnings
+    }
+
+    /// Creates a `Tokenizer` for a piece of a larger text that starts at the given line,
+    /// continuing with the context left by the previous piece.
+    ///
+    /// Unless the piece is the `last` one, the tokenizer stops at its end
+    /// without closing the line or the indentation levels.
+    pub(crate) fn resume(
+        text: &'a str,
+        line: usize,
+        context: LineContext,
+        last: bool,
+        config: TokenizerConfig,
+    ) -> Self {
+        let mut tokenizer = Tokenizer::with_config(text, config);
+        tokenizer.iter.set_line(line);
+        tokenizer.ind_stack = context.ind_stack;
+        tokenizer.brackets_stack = context.brackets_stack;
+        tokenizer.block_start = context.block_start;
+        tokenizer.partial = !last;
+        tokenizer
+    }
+
+    /// Returns the context for the next piece of the text.
+    pub(crate) fn into_context(self) -> LineContext {
+        LineContext {
+            ind_stack: self.ind_stack,
+            brackets_stack: self.brackets_stack,
+            block_start: self.block_start,
+        }
+    }
+
+    /// Checks if the whole text has been read.
+    pub(crate) fn at_end(&mut self) -> bool {
+        self.iter.peek().is_none()
+    }
+
+    /// Returns the next token together with its location.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokenizer_py::{Token, Tokenizer};
+    ///
+    /// let mut tokenizer = Tokenizer::new("pass");
+    /// let info = tokenizer.next_info().unwrap().unwrap();
+    ///
+    /// assert_eq!(info.token, Token::Name("pass".to_string()));
+    /// assert_eq!((info.start, info.end), ((1, 0), (1, 4)));
+    /// assert_eq!(info.line, "pass");
+    /// ```
+    pub fn next_info(&mut self) -> Option<Result<TokenInfo>> {
+        self.next_spanned().map(|spanned| {
+            spanned.map(|spanned| {
+                let mut info = TokenInfo::new(spanned.token.into(), spanned.start, spanned.end);
+                info.line = self.line(spanned.line_offset, spanned.end_offset).to_owned();
+                info
+            })
+        })
+    }
+
+    /// Returns the next token borrowed from the text, without copying it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokenizer_py::{TokenRef, Tokenizer};
+    ///
+    /// let text = String::from("answer = 42");
+    /// let mut tokenizer = Tokenizer::new(&text);
+    ///
+    /// assert_eq!(tokenizer.next_ref(), Some(Ok(TokenRef::Name("answer"))));
+    /// assert_eq!(tokenizer.next_ref(), Some(Ok(TokenRef::OP("="))));
+    /// assert_eq!(tokenizer.next_ref(), Some(Ok(TokenRef::Number("42"))));
+    /// ```
+    pub fn next_ref(&mut self) -> Option<Result<TokenRef<'a>>> {
+        self.next_spanned().map(|spanned| spanned.map(|spanned| spanned.token))
+    }
+
+    /// Returns the next token together with its span.
+    pub(crate) fn next_spanned(&mut self) -> Option<Result<Spanned<'a>>> {
+        loop {
+            // The tokens of an f-string are held back until it ends,
+            // since they all refer to the lines it spans
+            if self.fstrings.is_empty() || self.finished {
+                if let Some(spanned) = self.pending.pop_front() {
+                    return Some(Ok(spanned));
+                }
+            }
+            if self.finished {
+                return None;
+            }
+            if let Err(err) = self.advance() {
+                self.finished = true;
+                if !self.fstrings.is_empty() {
+                    self.pending.clear();
+                }
+                return Some(Err(err));
+            }
+        }
+    }
+
+    /// Queues a token that starts at `start` on the line beginning at `line_offset`
+    /// and ends at the current position.
+    fn push(&mut self, token: TokenRef<'a>, start: (usize, usize), line_offset: usize) {
+        let end = self.iter.pos();
+        self.push_span(token, start, end, line_offset);
+    }
+
+    /// Queues a token with an explicit span.
+    fn push_span(
+        &mut self,
+        token: TokenRef<'a>,
+        start: (usize, usize),
+        end: (usize, usize),
+        line_offset: usize,
+    ) {
+        let end_offset = self.iter.offset();
+        let line_offset =
+            self.joined_line.take().map_or(line_offset, |joined| joined.min(line_offset));
+        match token {
+            TokenRef::Comment(_)
+            | TokenRef::NL(_)
+            | TokenRef::NewLine(_)
+            | TokenRef::Indent(_)
+            | TokenRef::Dedent
+            | TokenRef::Whitespace(_)
+            | TokenRef::Continuation(_) => {}
+            token => self.block_start = token == TokenRef::OP(":"),
+        }
+        self.pending.push_back(Spanned {
+            token,
+            start,
+            end,
+            line_offset,
+            end_offset,
+        });
+    }
+
+    /// Returns the physical lines from `line_offset` up to the end of the line
+    /// containing `end_offset`.
+    fn line(&self, line_offset: usize, end_offset: usize) -> &'a str {
+        let src = self.iter.src();
+        let end = if end_offset > line_offset && src[..end_offset].ends_with(is_line_ending) {
+            end_offset
+        } else {
+            match src[end_offset..].find(is_line_ending) {
+                Some(i) if src[end_offset + i..].starts_with("\r\n") => end_offset + i + 2,
+                Some(i) => end_offset + i + 1,
+                None => src.len(),
+            }
+        };
+        &src[line_offset..end]
+    }
+
+    /// Reads the next piece of the text, queueing the tokens found in it.
+    fn advance(&mut self) -> Result<()> {
+        if !self.fstrings.is_empty() && self.at_end() {
+            let err = self.unterminated_fstring();
+            return self.recover_fstrings(err);
+        }
+        if self.partial && self.at_end() {
+            // The rest of the line is in the next piece of the text
+            self.finished = true;
+            return Ok(());
+        }
+        if self.at_line_start {
+            self.at_line_start = false;
+            if self.brackets_stack.is_empty() {
+                return self.indentation();
+            }
+            return Ok(());
+        }
+
+        let start = self.iter.pos();
+        let start_offset = self.iter.offset();
+        let line_offset = self.iter.line_offset();
+        let c = match self.iter.peek() {
+            Some(&c) => c,
+            None => {
+                return self.end_of_text();
+            }
+        };
+
+        // The literal text of an f-string and the format specs of its fields are not code
+        let middle = self.fstrings.last().is_some_and(|fstring| {
+            fstring.fields.is_empty()
+                || fstring.fields.last() == Some(&true)
+                    && self.brackets_stack.len() == fstring.field_depth()
+        });
+        let result = if middle {
+            self.fstring_middle(start, line_offset)
+        } else {
+            self.collect(c, start, start_offset, line_offset)
+        };
+        match result {
+            Err(err)
+                if matches!(
+                    err.kind,
+                    ErrorKind::UnterminatedFString
+                        | ErrorKind::UnterminatedTripleQuotedFString
+                        | ErrorKind::UnterminatedTString
+                        | ErrorKind::UnterminatedTripleQuotedTString
+                ) && !self.fstrings.is_empty() =>
+            {
+                self.recover_fstrings(err)
+            }
+            // The code of a replacement field runs into the end of the f-string
+            Err(err)
+                if self.config.recover
+                    && (middle || err.kind == ErrorKind::ExpectingClosingBrace) =>
+            {
+                self.fstring_error_token(err, start, start_offset, line_offset);
+                Ok(())
+            }
+            Err(err) if self.config.recover => {
+                self.error_token(err, start, start_offset, line_offset);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Collects the literal text or the format spec of the innermost f-string,
+    /// queueing it with the brace or the quotes that end it.
+    fn fstring_middle(&mut self, start: (usize, usize), line_offset: usize) -> Result<()> {
+        let fstring = self.fstrings.last().unwrap();
+        let middle = collect_fstring_middle(
+            &mut self.iter,
+            fstring.quote,
+            fstring.raw,
+            !fstring.fields.is_empty(),
+            fstring.template,
+            fstring.start,
+            &mut self.warnings,
+        )?;
+        let depth = self.fstrings.len() - 1;
+        if depth > 0 {
+            // The f-string is in the code of a replacement field of another one
+            self.require_field_code(middle, &self.fstrings[..depth], start)?;
+        }
+        if !middle.is_empty() {
+            let token = if fstring.template {
+                TokenRef::TStringMiddle(middle)
+            } else {
+                TokenRef::FStringMiddle(middle)
+            };
+            self.push(token, start, line_offset);
+        }
+
+        let start = self.iter.pos();
+        let start_offset = self.iter.offset();
+        let line_offset = self.iter.line_offset();
+        let fstring = self.fstrings.last_mut().unwrap();
+        match self.iter.next() {
+            // A replacement field, nested in the format spec if there is one
+            Some('{') => {
+                self.brackets_stack.push(('{', start));
+                fstring.fields.push(false);
+            }
+            Some('}') => {
+                self.brackets_stack.pop();
+                fstring.fields.pop();
+            }
+            _ => {
+                for _ in 1..fstring.quote.len() {
+                    self.iter.next();
+                }
+                let end = self.iter.slice_from(start_offset);
+                let token = if fstring.template {
+                    TokenRef::TStringEnd(end)
+                } else {
+                    TokenRef::FStringEnd(end)
+                };
+                self.push(token, start, line_offset);
+                self.pop_fstring();
+                return Ok(());
+            }
+        }
+        let token = TokenRef::OP(self.iter.slice_from(start_offset));
+        self.push(token, start, line_offset);
+        Ok(())
+    }
+
+    /// Pops the innermost f-string, which has ended or can not be ended.
+    ///
+    /// When the outermost f-string is popped, its held back tokens are released,
+    /// as a single `Token::String` if the target version of Python
+    /// does not split f-strings into tokens.
+    fn pop_fstring(&mut self) {
+        let fstring = self.fstrings.pop().unwrap();
+        self.brackets_stack.truncate(fstring.bracket_depth);
+        if !self.fstrings.is_empty() {
+            return;
+        }
+        let end_offset = self.iter.offset();
+        if !self.config.supports(Feature::FStringTokens) {
+            // Older versions of Python keep the whole f-string in one token
+            self.pending.clear();
+            let token = TokenRef::String(self.iter.slice_from(fstring.start_offset));
+            self.push(token, fstring.start, fstring.line_offset);
+            return;
+        }
+        // Every part of the f-string refers to all the lines it spans
+        for spanned in self.pending.iter_mut() {
+            spanned.line_offset = fstring.line_offset;
+            spanned.end_offset = end_offset;
+        }
+    }
+
+    /// Makes the error for the innermost f-string, which is not closed.
+    fn unterminated_fstring(&mut self) -> TokenizeError {
+        let fstring = self.fstrings.last().unwrap();
+        let kind = unterminated(fstring.quote.len() == 3, fstring.template);
+        TokenizeError::new(kind, fstring.start, self.iter.pos())
+    }
+
+    /// Checks a piece of the code of a replacement field, which starts at `start`
+    /// and is in the f-strings `enclosing`, against the target version of Python.
+    ///
+    /// Before Python 3.12 that code is text of the f-string,
+    /// so it can not have backslashes or comments,
+    /// nor the quotes of the f-strings it is in.
+    fn require_field_code(
+        &self,
+        code: &str,
+        enclosing: &[FStringMode<'_>],
+        start: (usize, usize),
+    ) -> Result<()> {
+        if self.config.supports(Feature::FStringTokens) {
+            return Ok(());
+        }
+        // Those quotes would end the f-string
+        let reused_quote = enclosing.iter().any(|fstring| code.contains(fstring.quote));
+        if reused_quote || code.contains(['\\', '#']) {
+            return self.config.require(Feature::FStringTokens, start, self.iter.pos());
+        }
+        Ok(())
+    }
+
+    /// Gives up on the open f-strings after an unterminated f-string error.
+    ///
+    /// When recovering, the error is recorded and the text of the f-strings
+    /// becomes a single `Token::ErrorToken` instead of their held back tokens.
+    /// A single-quoted f-string is taken to end with the line it starts on,
+    /// and the tokenizer goes on from there.
+    fn recover_fstrings(&mut self, err: TokenizeError) -> Result<()> {
+        if !self.config.recover {
+            return Err(err);
+        }
+        let fstring = self.fstrings.drain(..).next().unwrap();
+        self.brackets_stack.truncate(fstring.bracket_depth);
+        self.pending.clear();
+        if fstring.quote.len() == 1 {
+            self.iter = fstring.start_iter;
+            while self.iter.next_if(|c| !is_line_ending(*c)).is_some() {}
+            // What comes after is tokenized again
+            let end = self.iter.pos();
+            self.errors.retain(|err| err.start < end);
+            self.warnings.retain(|warning| warning.start < end);
+        }
+        self.errors.push(err);
+        let token = TokenRef::ErrorToken(self.iter.slice_from(fstring.start_offset));
+        self.blank_line = false;
+        self.push(token, fstring.start, fstring.line_offset);
+        Ok(())
+    }
+
+    /// Collects the token that starts with `c` at `start`, queueing it.
+    fn collect(
+        &mut self,
+        c: char,
+        start: (usize, usize),
+        start_offset: usize,
+        line_offset: usize,
+    ) -> Result<()> {
+        // The f-strings whose replacement fields the token is in
+        let depth = self.fstrings.len();
+        let fstring_quote = self.fstrings.last().map(|fstring| fstring.quote);
+        // Whether the tokenizer is at the top level of a replacement field of an f-string
+        let field_level = self.fstrings.last().is_some_and(|fstring| {
+            !fstring.fields.is_empty() && self.brackets_stack.len() == fstring.field_depth()
+        });
+        let iter = &mut self.iter;
+        let token = match c {
+            'r' | 'f' | 'b' | 'u' | 't' | 'R' | 'F' | 'B' | 'U' | 'T' => {
+                // A string prefix is a run of these letters right before a quote
+                let prefix_len = iter.clone().take_while(|c| "rfbutRFBUT".contains(*c)).count();
+                if !matches!(iter.clone().nth(prefix_len), Some('\'' | '"')) {
+                    TokenRef::Name(collect_name(iter, start_offset))
+                } else {
+                    let start_iter = iter.clone();
+                    iter.nth(prefix_len - 1);
+                    let prefix = iter.slice_from(start_offset);
+                    let template = TSTRING_PREFIXES.iter().any(|p| prefix.eq_ignore_ascii_case(p));
+                    if !template && !FSTRING_PREFIXES.iter().any(|p| prefix.eq_ignore_ascii_case(p))
+                    {
+                        TokenRef::String(collect_field_string(
+                            iter,
+                            start_offset,
+                            fstring_quote,
+                            &mut self.warnings,
+                        )?)
+                    } else {
+                        let feature =
+                            if template { Feature::TemplateStrings } else { Feature::FStrings };
+                        self.config.require(feature, start, iter.pos())?;
+                        let raw = prefix.contains(['r', 'R']);
+                        let quote = quote_at(&iter.src()[iter.offset()..]);
+                        for _ in 0..quote.len() {
+                            iter.next();
+                        }
+                        let start_text = iter.slice_from(start_offset);
+                        let token = if template {
+                            TokenRef::TStringStart(start_text)
+                        } else {
+                            TokenRef::FStringStart(start_text)
+                        };
+                        self.fstrings.push(FStringMode {
+                            quote,
+                            raw,
+                            template,
+                            bracket_depth: self.brackets_stack.len(),
+                            fields: Vec::new(),
+                            start,
+                            start_offset,
+                            line_offset,
+                            start_iter,
+                        });
+                        token
+                    }
+                }
+            }
+            '\'' | '"' => TokenRef::String(collect_field_string(
+                iter,
+                start_offset,
+                fstring_quote,
+                &mut self.warnings,
+            )?),
+            '0'..='9' => {
+                let number = collect_number(iter, start_offset)?;
+                if number.contains('_') {
+                    self.config.require(Feature::NumericUnderscores, start, iter.pos())?;
+                }
+                TokenRef::Number(number)
+            }
+            '\n' | '\r' => {
+                iter.next();
+                if c == '\r' {
+                    iter.next_if(|c| *c == '\n');
+                }
+                let newline = iter.slice_from(start_offset);
+                let token = if self.blank_line || !self.brackets_stack.is_empty() {
+                    TokenRef::NL(newline)
+                } else {
+                    TokenRef::NewLine(newline)
+                };
+                // The newline ends on its own line, not at the start of the next one
+                let end = (start.0, start.1 + newline.len());
+                self.push_span(token, start, end, line_offset);
+                self.at_line_start = true;
+                self.blank_line = true;
+                return Ok(());
+            }
+            '#' => {
+                let token = TokenRef::Comment(collect_comment(iter));
+                if depth > 0 {
+                    self.require_field_code("#", &self.fstrings, start)?;
+                }
+                self.push(token, start, line_offset);
+                return Ok(());
+            }
+            c if OPERATORS.contains(c) => {
+                let operator = iter.next().unwrap();
+                match operator {
+                    // The replacement field ends, or its format spec starts
+                    '}' if field_level => {
+                        self.brackets_stack.pop();
+                        self.fstrings.last_mut().unwrap().fields.pop();
+                    }
+                    ':' if field_level => {
+                        *self.fstrings.last_mut().unwrap().fields.last_mut().unwrap() = true;
+                        let token = TokenRef::OP(iter.slice_from(start_offset));
+                        self.blank_line = false;
+                        self.push(token, start, line_offset);
+                        return Ok(());
+                    }
+                    '[' | '{' | '(' => self.brackets_stack.push((operator, start)),
+                    ']' | '}' | ')' => match self.brackets_stack.last() {
+                        Some(&(opening, _)) if closing_bracket(opening) == operator => {
+                            self.brackets_stack.pop();
+                        }
+                        Some(&(opening, opening_start)) => {
+                            // The wrong bracket is taken to close the open one,
+                            // unless that one opens a replacement field
+                            if !field_level {
+                                self.brackets_stack.pop();
+                            }
+                            let kind = ErrorKind::MismatchedBracket {
+                                opening,
+                                opening_start,
+                                closing: operator,
+                            };
+                            return Err(TokenizeError::new(kind, start, iter.pos()));
+                        }
+                        None => {
+                            let kind = ErrorKind::UnmatchedBracket(operator);
+                            return Err(TokenizeError::new(kind, start, iter.pos()));
+                        }
+                    },
+                    '.' if matches!(iter.peek(), Some('0'..='9')) => {
+                        let number = collect_number(iter, start_offset)?;
+                        if number.contains('_') {
+                            self.config.require(Feature::NumericUnderscores, start, iter.pos())?;
+                        }
+                        let token = TokenRef::Number(number);
+                        self.blank_line = false;
+                        self.push(token, start, line_offset);
+                        return Ok(());
+                    }
+                    _ => {}
+                }
+                let operator = collect_operator(iter, operator)?;
+                if operator == ":=" {
+                    self.config.require(Feature::AssignmentExpressions, start, iter.pos())?;
+                }
+                // A lone `!` came with the conversions of f-string replacement fields,
+                // where older versions of Python only have it before the conversion
+                if operator == "!" && !field_level && !self.config.supports(Feature::FStringTokens)
+                {
+                    let kind = ErrorKind::InvalidCharacter('!');
+                    return Err(TokenizeError::new(kind, start, iter.pos()));
+                }
+                TokenRef::OP(operator)
+            }
+            c if is_name_start(c) => TokenRef::Name(collect_name(iter, start_offset)),
+            ' ' | '\t' | '\x0c' => {
+                let whitespace = collect_whitespace(iter);
+                if self.config.lossless {
+                    self.push(TokenRef::Whitespace(whitespace), start, line_offset);
+                }
+                return Ok(());
+            }
+            '\\' => {
+                iter.next();
+                // The joined newline belongs to the continuation, so the line goes on
+                let joined = match iter.next_if(|c| *c == '\r' || *c == '\n') {
+                    Some('\r') => {
+                        iter.next_if(|c| *c == '\n');
+                        true
+                    }
+                    Some(_) => true,
+                    None => false,
+                };
+                // Even a piece of a larger text can not end here,
+                // so that the joined line is read together with this one
+                if iter.peek().is_none() {
+                    return Err(TokenizeError::new(
+                        ErrorKind::UnexpectedEof,
+                        start,
+                        iter.pos(),
+                    ));
+                }
+                if !joined {
+                    let kind = ErrorKind::UnexpectedCharacterAfterContinuation;
+                    return Err(TokenizeError::new(kind, start, iter.pos()));
+                }
+                if self.config.lossless {
+                    let token = TokenRef::Continuation(iter.slice_from(start_offset));
+                    self.push(token, start, line_offset);
+                }
+                // A line with nothing but the continuation is kept in the line of the next token
+                let before = &self.iter.src()[line_offset..start_offset];
+                if before.chars().all(|c| matches!(c, ' ' | '\t' | '\x0c')) {
+                    self.joined_line.get_or_insert(line_offset);
+                }
+                if depth > 0 {
+                    self.require_field_code("\\", &self.fstrings, start)?;
+                }
+                return Ok(());
+            }
+            // Any other character can not start a token, nor separate tokens
+            c => {
+                iter.next();
+                let kind = ErrorKind::InvalidCharacter(c);
+                return Err(TokenizeError::new(kind, start, iter.pos()));
+            }
+        };
+        if let TokenRef::String(code)
+        | TokenRef::FStringStart(code)
+        | TokenRef::TStringStart(code) = token
+        {
+            if depth > 0 {
+                self.require_field_code(code, &self.fstrings[..depth], start)?;
+            }
+        }
+        self.blank_line = false;
+        self.push(token, start, line_offset);
+        Ok(())
+    }
+
+    /// Records an error and queues a `Token::ErrorToken` for the text from `start`
+    /// up to the next place the tokenizer can carry on from,
+    /// which is a whitespace, a bracket, a comma or the end of the line.
+    /// An invalid character is an error token of its own.
+    fn error_token(
+        &mut self,
+        err: TokenizeError,
+        start: (usize, usize),
+        start_offset: usize,
+        line_offset: usize,
+    ) {
+        let invalid_character = matches!(err.kind, ErrorKind::InvalidCharacter(_));
+        self.errors.push(err);
+        let iter = &mut self.iter;
+        if iter.offset() == start_offset {
+            iter.next();
+        }
+        while let Some(&c) = iter.peek().filter(|_| !invalid_character) {
+            match c {
+                c if c.is_whitespace() => break,
+                '(' | ')' | '[' | ']' | '{' | '}' | ',' => break,
+                // A quoted part is skipped as a whole, so that its closing quote
+                // does not open another string
+                '\'' | '"' => {
+                    iter.next();
+                    while iter.next_if(|next| *next != c && !is_line_ending(*next)).is_some() {}
+                    iter.next_if(|next| *next == c);
+                }
+                _ => {
+                    iter.next();
+                }
+            }
+        }
+        let token = TokenRef::ErrorToken(iter.slice_from(start_offset));
+        self.blank_line = false;
+        self.push(token, start, line_offset);
+    }
+
+    /// Records an error in the literal text or a format spec of an f-string,
+    /// or at its quotes in a replacement field,
+    /// and queues a `Token::ErrorToken` for the text from `start` up to the error.
+    ///
+    /// A single `}` is part of the error token, and a replacement field
+    /// that is not closed before the end of the f-string is given up on.
+    fn fstring_error_token(
+        &mut self,
+        err: TokenizeError,
+        start: (usize, usize),
+        start_offset: usize,
+        line_offset: usize,
+    ) {
+        match err.kind {
+            ErrorKind::SingleClosingBrace => {
+                self.iter.next();
+            }
+            ErrorKind::ExpectingClosingBrace => {
+                let fstring = self.fstrings.last_mut().unwrap();
+                self.brackets_stack.truncate(fstring.bracket_depth);
+                fstring.fields.clear();
+            }
+            _ => {}
+        }
+        self.errors.push(err);
+        let text = self.iter.slice_from(start_offset);
+        if !text.is_empty() {
+            self.push(TokenRef::ErrorToken(text), start, line_offset);
+        }
+    }
+
+    /// Handles the indentation at the start of a line,
+    /// queueing `Token::Indent` or `Token::Dedent` tokens if it has changed.
+    ///
+    /// An indentation error does not change the tokens that are queued,
+    /// so when recovering it is only recorded.
+    fn indentation(&mut self) -> Result<()> {
+        let start = self.iter.pos();
+        let line_offset = self.iter.line_offset();
+        let new_ind = collect_indent(&mut self.iter);
+
+        // Blank lines and lines with only a comment do not change the indentation
+        if let None | Some('\n' | '\r' | '#') = self.iter.peek() {
+            if self.config.lossless && !new_ind.is_empty() {
+                self.push(TokenRef::Whitespace(new_ind), start, line_offset);
+            }
+            return Ok(());
+        }
+
+        let indentation = Indentation::of(new_ind);
+        let mut top = *self.ind_stack.last().unwrap();
+        let mut error = None;
+        if indentation.col > top.col {
+            if indentation.alt_col <= top.alt_col {
+                error = Some(ErrorKind::InconsistentTabs);
+            } else if !self.block_start {
+                error = Some(ErrorKind::UnexpectedIndent);
+            }
+            self.ind_stack.push(indentation);
+            self.push(TokenRef::Indent(new_ind), start, line_offset);
+        } else {
+            if self.config.lossless && !new_ind.is_empty() {
+                self.push(TokenRef::Whitespace(new_ind), start, line_offset);
+            }
+            while indentation.col < top.col {
+                self.ind_stack.pop();
+                let pos = self.iter.pos();
+                self.push_span(TokenRef::Dedent, pos, pos, line_offset);
+                top = *self.ind_stack.last().unwrap();
+            }
+            if indentation.col != top.col {
+                error = Some(ErrorKind::UnindentMismatch);
+            } else if indentation.alt_col != top.alt_col {
+                error = Some(ErrorKind::InconsistentTabs);
+            }
+        }
+
+        match error {
+            Some(kind) => {
+                let err = TokenizeError::new(kind, start, self.iter.pos());
+                if !self.config.recover {
+                    // The error comes before the tokens of the line
+                    self.pending.clear();
+                    return Err(err);
+                }
+                self.errors.push(err);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Finishes the text, closing the last line and all open indentation levels.
+    ///
+    /// A bracket left open is an error, after which the text is finished
+    /// as if it was closed when recovering, ending its logical line.
+    fn end_of_text(&mut self) -> Result<()> {
+        let mut unclosed = false;
+        if let Some(&(opening, (line, column))) = self.brackets_stack.last() {
+            let kind = ErrorKind::UnclosedBracket(opening);
+            let err = TokenizeError::new(kind, (line, column), (line, column + 1));
+            if !self.config.recover {
+                return Err(err);
+            }
+            self.errors.push(err);
+            self.brackets_stack.clear();
+            unclosed = true;
+        }
+        self.finished = true;
+        let mut pos = self.iter.pos();
+        let line_offset = self.iter.line_offset();
+        // The whitespace of a last line left open is kept in the line of the end marker
+        let mut end_line_offset = line_offset;
+
+        // A text that does not end with a newline still ends its last line,
+        // as a blank line if it only has a comment
+        let comment_line = self.iter.src()[line_offset..].trim_start().starts_with('#');
+        if !self.blank_line || comment_line {
+            let token = if self.brackets_stack.is_empty() && (!self.blank_line || unclosed) {
+                TokenRef::NewLine("")
+            } else {
+                TokenRef::NL("")
+            };
+            self.push_span(token, pos, (pos.0, pos.1 + 1), line_offset);
+            pos = (pos.0 + 1, 1);
+            end_line_offset = self.iter.src().len();
+        } else if unclosed {
+            // The physical lines in the brackets already ended with NL tokens
+            self.push_span(TokenRef::NewLine(""), pos, pos, line_offset);
+        }
+        while self.ind_stack.last().unwrap().col > 0 {
+            self.ind_stack.pop();
+            self.push_span(TokenRef::Dedent, pos, pos, end_line_offset);
+        }
+        self.push_span(TokenRef::EndMarker, pos, pos, end_line_offset);
+        Ok(())
+    }
+}
+
+/// Returns the bracket that closes the given opening bracket.
+fn closing_bracket(opening: char) -> char {
+    match opening {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
+
+impl Iterator for Tokenizer<'_> {
+    type Item = Result<Token>;
+
+    /// Returns the next token.
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_ref().map(|token| token.map(Token::from))
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{Token, TokenInfo};
+
+/// Returns the source text of a token that has one.
+fn text(token: &Token) -> &str {
+    match token {
+        Token::Name(text)
+        | Token::Number(text)
+        | Token::String(text)
+        | Token::OP(text)
+        | Token::Indent(text)
+        | Token::Comment(text)
+        | Token::FStringStart(text)
+        | Token::FStringMiddle(text)
+        | Token::FStringEnd(text)
+        | Token::TStringStart(text)
+        | Token::TStringMiddle(text)
+        | Token::TStringEnd(text)
+        | Token::Whitespace(text)
+        | Token::Continuation(text)
+        | Token::ErrorToken(text) => text,
+        Token::EndMarker | Token::NewLine | Token::Dedent | Token::NL | Token::Encoding(_) => "",
+    }
+}
+
+/// Returns the characters of `line` from the column `from` up to the column `to`.
+fn columns(line: &str, from: usize, to: usize) -> String {
+    line.chars().skip(from).take(to.saturating_sub(from)).collect()
+}
+
+/// Checks if the text is made of whitespace that can separate tokens on a line.
+fn is_blank(text: &str) -> bool {
+    text.chars().all(|c| matches!(c, ' ' | '\t' | '\x0c'))
+}
+
+/// Checks if the text ends a line with a backslash continuation.
+fn is_continuation(text: &str) -> bool {
+    let joined = strip_line_ending(text);
+    joined.len() < text.len() && joined.strip_suffix('\\').is_some_and(is_blank)
+}
+
+/// Returns the last physical line of a token's `line`.
+fn last_line(line: &str) -> &str {
+    let trimmed = strip_line_ending(line);
+    &line[trimmed.rfind(['\n', '\r']).map_or(0, |i| i + 1)..]
+}
+
+/// Returns the physical line of a token's `line` that the row `row` is on, if it has it.
+///
+/// The `line` ends with the line the token ends on, and starts with the lines before
+/// the token that only hold a backslash continuation.
+fn line_at(info: &TokenInfo, row: usize) -> Option<&str> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for (i, c) in info.line.char_indices() {
+        if c == '\n' || c == '\r' && !info.line[i + 1..].starts_with('\n') {
+            lines.push(&info.line[start..=i]);
+            start = i + 1;
+        }
+    }
+    if start < info.line.len() {
+        lines.push(&info.line[start..]);
+    }
+    // A token that takes in a line ending ends at the start of the next row
+    let last_row =
+        if info.end.1 == 0 && info.end != info.start { info.end.0 - 1 } else { info.end.0 };
+    let index = (lines.len() + row).checked_sub(last_row + 1)?;
+    lines.get(index).copied()
+}
+
+/// Removes the `\n`, `\r\n` or `\r` that ends the text, if any.
+fn strip_line_ending(text: &str) -> &str {
+    let text = text.strip_suffix('\n').unwrap_or(text);
+    text.strip_suffix('\r').unwrap_or(text)
+}
+
+/// Converts tokens with their positions back into source text,
+/// like Python's `tokenize.untokenize` given full `TokenInfo` records.
+///
+/// The whitespace between tokens is taken from the `line` of each token,
+/// so the tokens of [`tokenize_info`](crate::tokenize_info) turn back into
+/// exactly the text they came from. Where the `line` does not have it,
+/// for example after tokens were edited, spaces are used instead.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{tokenize_info, untokenize, Token};
+///
+/// let source = "def f(x):\n\treturn x  # identity\n";
+/// let mut tokens = tokenize_info(source).unwrap();
+///
+/// assert_eq!(untokenize(&tokens), source);
+///
+/// tokens[1].token = Token::Name("g".to_string());
+/// assert_eq!(untokenize(&tokens), "def g(x):\n\treturn x  # identity\n");
+/// ```
+pub fn untokenize<'t>(tokens: impl IntoIterator<Item = &'t TokenInfo>) -> String {
+    let mut source = String::new();
+    let (mut prev_row, mut prev_col) = (1, 0);
+    let mut prev_line = "";
+
+    for info in tokens {
+        let (row, col) = info.start;
+        if let Token::Encoding(_) = info.token {
+            continue;
+        }
+
+        // Lines joined with a backslash leave a gap between the rows of two tokens
+        if row > prev_row {
+            for joined_row in prev_row..row {
+                let rest = match line_at(info, joined_row) {
+                    Some(line) => line.to_owned(),
+                    None if joined_row == prev_row => columns(prev_line, prev_col, usize::MAX),
+                    None => String::new(),
+                };
+                if is_continuation(&rest) {
+                    source.push_str(&rest);
+                } else {
+                    source.push_str("\\\n");
+                }
+            }
+            prev_col = 0;
+        }
+        if row >= prev_row && col > prev_col {
+            let gap = columns(line_at(info, row).unwrap_or(""), prev_col, col);
+            if gap.chars().count() == col - prev_col && is_blank(&gap) {
+                source.push_str(&gap);
+            } else {
+                source.push_str(&" ".repeat(col - prev_col));
+            }
+        }
+
+        match info.token {
+            Token::EndMarker => break,
+            Token::NewLine | Token::NL => {
+                match line_at(info, row) {
+                    Some(line) => source.push_str(&columns(line, col, usize::MAX)),
+                    // Closing a line left open at the end of the text adds no newline
+                    None if info.start == info.end => {}
+                    None => source.push('\n'),
+                }
+                prev_row = row + 1;
+                prev_col = 0;
+            }
+            ref token => {
+                source.push_str(text(token));
+                prev_row = info.end.0;
+                prev_col = info.end.1;
+            }
+        }
+        prev_line = last_line(&info.line);
+    }
+    source
+}
+
+/// Converts tokens without positions back into source text,
+/// like the compatibility mode of Python's `tokenize.untokenize`.
+///
+/// The result is not the original text, but valid Python that tokenizes
+/// into the same tokens, with as little whitespace as possible.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{tokenize, untokenize_compat};
+///
+/// let tokens = tokenize("if  x :\n    y = 'a'  'b'\n").unwrap();
+///
+/// assert_eq!(untokenize_compat(&tokens), "if x :\n    y ='a' 'b'\n");
+/// ```
+pub fn untokenize_compat<'t>(tokens: impl IntoIterator<Item = &'t Token>) -> String {
+    let mut source = String::new();
+    let mut indents: Vec<&str> = Vec::new();
+    let mut start_line = false;
+    let mut prev_string = false;
+
+    for token in tokens {
+        match token {
+            // The compact text has whitespace of its own
+            Token::Encoding(_) | Token::Whitespace(_) | Token::Continuation(_) => continue,
+            Token::EndMarker => break,
+            Token::Indent(indent) => {
+                indents.push(indent);
+                continue;
+            }
+            Token::Dedent => {
+                indents.pop();
+                continue;
+            }
+            Token::NewLine | Token::NL => {
+                source.push('\n');
+                start_line = true;
+                prev_string = false;
+                continue;
+            }
+            _ => {}
+        }
+
+        if start_line {
+            source.push_str(indents.last().unwrap_or(&""));
+            start_line = false;
+        }
+        match token {
+            Token::Name(text) | Token::Number(text) => {
+                source.push_str(text);
+                source.push(' ');
+            }
+            // Adjacent strings need a space between them to stay separate tokens
+            Token::String(text) => {
+                if prev_string {
+                    source.push(' ');
+                }
+                source.push_str(text);
+            }
+            token => source.push_str(text(token)),
+        }
+        prev_string = matches!(token, Token::String(_));
+    }
+    source
+}
@@ -0,0 +1,100 @@
+use std::fmt::{Display, Formatter};
+
+/// The kinds of warnings about code that tokenizes but is likely a mistake.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WarningKind {
+    /// A backslash that does not start an escape sequence, such as the `\d` of `"\d"`.
+    InvalidEscapeSequence(String),
+    /// An octal escape sequence above `\377`, such as `\777`.
+    InvalidOctalEscapeSequence(String),
+}
+
+impl WarningKind {
+    /// Returns the stable code of the kind of warning.
+    pub fn code(&self) -> &'static str {
+        match self {
+            WarningKind::InvalidEscapeSequence(_) => "W0101",
+            WarningKind::InvalidOctalEscapeSequence(_) => "W0102",
+        }
+    }
+}
+
+impl Display for WarningKind {
+    /// Writes the message of the warning, worded like the `SyntaxWarning` of CPython.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WarningKind::InvalidEscapeSequence(escape) => {
+                write!(f, "invalid escape sequence '{}'", escape)
+            }
+            WarningKind::InvalidOctalEscapeSequence(escape) => {
+                write!(f, "invalid octal escape sequence '{}'", escape)
+            }
+        }
+    }
+}
+
+/// A warning found during tokenization.
+///
+/// Like a [`TokenizeError`](crate::TokenizeError), it spans the source from `start`
+/// up to `end`, given as a line and a column that both start at 1.
+///
+/// # Examples
+///
+/// ```
+/// use tokenizer_py::{Tokenizer, TokenizeWarning, WarningKind};
+///
+/// let mut tokenizer = Tokenizer::new(r#"path = "C:\docs""#);
+/// for token in tokenizer.by_ref() {
+///     token.unwrap();
+/// }
+///
+/// assert_eq!(
+///     tokenizer.warnings(),
+///     [TokenizeWarning::new(
+///         WarningKind::InvalidEscapeSequence("\\d".to_string()),
+///         (1, 11),
+///         (1, 13)
+///     )]
+/// );
+/// assert_eq!(tokenizer.warnings()[0].to_string(), r"warning[W0101]: invalid escape sequence '\d' at 1:11");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenizeWarning {
+    /// The kind of the warning.
+    pub kind: WarningKind,
+    /// The position where the warning starts.
+    pub start: (usize, usize),
+    /// The position just past the end of the warning.
+    pub end: (usize, usize),
+}
+
+impl TokenizeWarning {
+    /// Creates a warning of the given kind spanning from `start` up to `end`.
+    pub fn new(kind: WarningKind, start: (usize, usize), end: (usize, usize)) -> Self {
+        TokenizeWarning { kind, start, end }
+    }
+
+    /// Returns the stable code of the warning.
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+
+    /// Returns the message of the warning.
+    pub fn message(&self) -> String {
+        self.kind.to_string()
+    }
+}
+
+impl Display for TokenizeWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (line, column) = self.start;
+        write!(
+            f,
+            "warning[{}]: {} at {}:{}",
+            self.code(),
+            self.kind,
+            line,
+            column
+        )
+    }
+}